@@ -0,0 +1,74 @@
+extern crate criterion;
+extern crate rmerger;
+extern crate rustc_hash;
+
+use std::collections::HashMap;
+use std::collections::hash_map::RandomState;
+use std::fs;
+use std::hash::BuildHasher;
+use std::process;
+use criterion::{ black_box, criterion_group, criterion_main, BatchSize, Criterion };
+use rustc_hash::FxBuildHasher;
+use rmerger::file::{ merge_files, MergeOptions };
+
+const KEYS: usize = 1_000_000;
+
+/// RDB v6 file of database 0 with the string keys `key:<i>` => `1` for `i` in `keys`
+fn synthetic_rdb(keys: std::ops::Range<usize>) -> Vec<u8> {
+    let mut rdb = b"REDIS0006\xfe\x00".to_vec();
+    for i in keys {
+        let key = format!("key:{:07}", i);
+        rdb.push(0x00);
+        rdb.push(key.len() as u8);
+        rdb.extend_from_slice(key.as_bytes());
+        rdb.extend_from_slice(b"\x01\x31");
+    }
+    rdb.push(0xff);
+    rdb.extend_from_slice(&[0x00; 8]);
+    rdb
+}
+
+/// the keys of two inputs overlapping by half into a map hashed by `S`, as the duplicate
+/// detection of PartRDB does; the number of duplicates found
+fn dedup<S: BuildHasher + Default>(keys: &[Vec<u8>]) -> usize {
+    let mut map: HashMap<&[u8], usize, S> = HashMap::default();
+    let mut duplicates = 0;
+    for (i, key) in keys.iter().chain(&keys[KEYS / 2..]).enumerate() {
+        if map.insert(key, i).is_some() {
+            duplicates += 1;
+        }
+    }
+    duplicates
+}
+
+/// the duplicate detection of a million keys with the hasher of PartRDB and with the default SipHash
+fn dedup_bench(c: &mut Criterion) {
+    let keys: Vec<Vec<u8>> = (0..KEYS).map(|i| format!("key:{:07}", i).into_bytes()).collect();
+    let mut group = c.benchmark_group("dedup");
+    group.sample_size(10);
+    group.bench_function("fxhash", |b| b.iter(|| dedup::<FxBuildHasher>(black_box(&keys))));
+    group.bench_function("siphash", |b| b.iter(|| dedup::<RandomState>(black_box(&keys))));
+    group.finish();
+}
+
+/// the merge of two files of a million keys each, half of them duplicates
+fn merge_bench(c: &mut Criterion) {
+    let dir = std::env::temp_dir().join(format!("rmerger-merge-bench-{}", process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let inputs = [dir.join("a.rdb"), dir.join("b.rdb")];
+    fs::write(&inputs[0], synthetic_rdb(0..KEYS)).unwrap();
+    fs::write(&inputs[1], synthetic_rdb(KEYS / 2..KEYS + KEYS / 2)).unwrap();
+
+    let mut group = c.benchmark_group("merge");
+    group.sample_size(10);
+    group.bench_function("million_keys", |b| b.iter_batched(
+        || MergeOptions { output_dir: dir.to_string_lossy().to_string(), ..MergeOptions::default() },
+        |opts| merge_files(&inputs, &opts).unwrap(),
+        BatchSize::PerIteration,
+    ));
+    group.finish();
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+criterion_group!(benches, dedup_bench, merge_bench);
+criterion_main!(benches);
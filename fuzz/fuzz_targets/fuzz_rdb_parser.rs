@@ -0,0 +1,16 @@
+// parse arbitrary bytes as an RDB file, and check that a parsed file serializes into bytes
+// which parse into the same RDB again
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use rmerger::parser::{ rdb, RDBSer };
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok((_, first)) = rdb(data) {
+        let mut ser = Vec::new();
+        if first.ser(&mut ser).is_ok() {
+            let (_, second) = rdb(&ser).expect("serialized RDB should parse");
+            assert_eq!(first, second);
+        }
+    }
+});
@@ -0,0 +1,81 @@
+/* generated by cbindgen from src/ffi.rs with the ffi feature */
+
+#ifndef RMERGER_H
+#define RMERGER_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * value type returned by `rmerger_get_value_type` for a record which does not exist
+ */
+#define RMERGER_NO_VALUE_TYPE 255
+
+/**
+ * parsed RDB file handed to C code, freed with `rmerger_free`
+ */
+typedef struct OpaqueRDB OpaqueRDB;
+
+/**
+ * parse the RDB file of `len` bytes at `data`, or NULL if it does not parse
+ *
+ * # Safety
+ *
+ * `data` must point to `len` readable bytes; they are copied and need not outlive the call
+ */
+struct OpaqueRDB *rmerger_parse(const uint8_t *data, size_t len);
+
+/**
+ * free an RDB returned by `rmerger_parse`; NULL is ignored
+ *
+ * # Safety
+ *
+ * `rdb` must be NULL or returned by `rmerger_parse` and not freed yet
+ */
+void rmerger_free(struct OpaqueRDB *rdb);
+
+/**
+ * number of records of database `db`, 0 if there is no such database
+ *
+ * # Safety
+ *
+ * `rdb` must be NULL or returned by `rmerger_parse` and not freed yet
+ */
+size_t rmerger_record_count(const struct OpaqueRDB *rdb, uint32_t db);
+
+/**
+ * key of record `idx` of database `db`, followed by a NUL byte, or NULL if there is no such
+ * record; the string is owned by `rdb`, and a key with NUL bytes is read up to
+ * `rmerger_get_key_len`
+ *
+ * # Safety
+ *
+ * `rdb` must be NULL or returned by `rmerger_parse` and not freed yet; the string is valid until
+ * `rdb` is freed
+ */
+const char *rmerger_get_key(const struct OpaqueRDB *rdb, uint32_t db, size_t idx);
+
+/**
+ * length in bytes of the key of record `idx` of database `db`, NUL bytes included and the
+ * terminating one excluded, or 0 if there is no such record
+ *
+ * # Safety
+ *
+ * `rdb` must be NULL or returned by `rmerger_parse` and not freed yet
+ */
+size_t rmerger_get_key_len(const struct OpaqueRDB *rdb, uint32_t db, size_t idx);
+
+/**
+ * value type byte of record `idx` of database `db`, as in the RDB file, or
+ * `RMERGER_NO_VALUE_TYPE` if there is no such record
+ *
+ * # Safety
+ *
+ * `rdb` must be NULL or returned by `rmerger_parse` and not freed yet
+ */
+uint8_t rmerger_get_value_type(const struct OpaqueRDB *rdb, uint32_t db, size_t idx);
+
+#endif /* RMERGER_H */
@@ -0,0 +1,79 @@
+use std::f64::consts::LN_2;
+use std::hash::{ Hash, Hasher };
+use rustc_hash::FxHasher;
+
+/// Bloom filter: `insert` may take an item which was never inserted for one already seen,
+/// with about the false positive rate given to `new` once `expected` items are in
+#[derive(Debug, Clone)]
+pub struct BloomFilter {
+    bits:   Vec<u64>,
+    hashes: u32,
+}
+
+impl BloomFilter {
+    /// filter sized for `expected` items and the false positive rate `fp_rate` (0 < fp_rate < 1)
+    pub fn new(expected: u64, fp_rate: f64) -> Self {
+        let n = expected.max(1) as f64;
+        let m = (-n * fp_rate.ln() / (LN_2 * LN_2)).ceil().max(64.0);
+        let k = ((m / n) * LN_2).round().clamp(1.0, 32.0);
+        BloomFilter {
+            bits:   vec![0; (m as usize).div_ceil(64)],
+            hashes: k as u32,
+        }
+    }
+
+    /// size of the bit array in bytes
+    pub fn size(&self) -> usize {
+        self.bits.len() * 8
+    }
+
+    /// bit positions of `item`, by double hashing of its one FxHash mixed into two
+    fn positions<T: Hash>(&self, item: &T) -> impl Iterator<Item = usize> {
+        let mut h = FxHasher::default();
+        item.hash(&mut h);
+        let h1 = mix(h.finish());
+        let h2 = mix(h1) | 1;
+        let m = (self.bits.len() * 64) as u64;
+        (0..self.hashes as u64).map(move |i| (h1.wrapping_add(i.wrapping_mul(h2)) % m) as usize)
+    }
+
+    /// whether `item` may have been inserted
+    pub fn contains<T: Hash>(&self, item: &T) -> bool {
+        self.positions(item).all(|p| self.bits[p / 64] & (1 << (p % 64)) != 0)
+    }
+
+    /// insert `item`, returning true if it may have been inserted before
+    pub fn insert<T: Hash>(&mut self, item: &T) -> bool {
+        let mut present = true;
+        for p in self.positions(item) {
+            let bit = 1 << (p % 64);
+            present &= self.bits[p / 64] & bit != 0;
+            self.bits[p / 64] |= bit;
+        }
+        present
+    }
+}
+
+/// the finalizer of SplitMix64, spreading every bit of `x` over the low bits taken by `%`
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// test
+#[test]
+fn bloom_filter_test() {
+    let mut bloom = BloomFilter::new(1000, 0.01);
+    assert_eq!(bloom.hashes, 7);
+    assert!(bloom.size() >= 1198);
+    // a false positive is possible while the filter fills up
+    let present = (0..1000u32).filter(|i| bloom.insert(&(0u32, i.to_string()))).count();
+    assert!(present < 10);
+    for i in 0..1000u32 {
+        assert!(bloom.contains(&(0u32, i.to_string())));
+        assert!(bloom.insert(&(0u32, i.to_string())));
+    }
+    let false_positives = (1000..11000u32).filter(|i| bloom.contains(&(0u32, i.to_string()))).count();
+    assert!(false_positives < 300);
+}
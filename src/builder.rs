@@ -0,0 +1,107 @@
+use super::parser::{ RDBSer, Record, OwnedRecord, ValueType, encode_length, record, parse_done };
+
+/// construct records in the simplest encodings: raw strings with the shortest length encoding
+/// and the plain list, set, sorted set and hash types
+pub struct RecordBuilder;
+
+fn push_string(v: &mut Vec<u8>, s: &[u8]) {
+    v.extend(encode_length(s.len() as u32));
+    v.extend_from_slice(s);
+}
+
+/// record of the serialized `value` of type `t` under `key`
+fn build<F: FnOnce(&mut Vec<u8>)>(t: ValueType, key: &str, value: F) -> OwnedRecord {
+    let mut v = vec![t as u8];
+    push_string(&mut v, key.as_bytes());
+    value(&mut v);
+    parse(&v)
+}
+
+fn parse(bytes: &[u8]) -> OwnedRecord {
+    parse_done(record(bytes)).expect("built record should parse").to_owned()
+}
+
+impl RecordBuilder {
+    pub fn string(key: &str, value: &str) -> OwnedRecord {
+        build(ValueType::String, key, |v| push_string(v, value.as_bytes()))
+    }
+
+    pub fn list(key: &str, values: &[&str]) -> OwnedRecord {
+        build(ValueType::List, key, |v| {
+            v.extend(encode_length(values.len() as u32));
+            for s in values {
+                push_string(v, s.as_bytes());
+            }
+        })
+    }
+
+    pub fn set(key: &str, members: &[&str]) -> OwnedRecord {
+        build(ValueType::Set, key, |v| {
+            v.extend(encode_length(members.len() as u32));
+            for s in members {
+                push_string(v, s.as_bytes());
+            }
+        })
+    }
+
+    /// scores are written as decimal strings, as in RDB versions before 8, in exponent notation if
+    /// they would not fit in the 252 bytes of a score string otherwise
+    pub fn sorted_set(key: &str, members: &[(&str, f64)]) -> OwnedRecord {
+        build(ValueType::Sortedset, key, |v| {
+            v.extend(encode_length(members.len() as u32));
+            for &(member, score) in members {
+                push_string(v, member.as_bytes());
+                let decimal = score.to_string();
+                let score = if decimal.len() < 253 { decimal } else { format!("{:e}", score) };
+                v.push(score.len() as u8);
+                v.extend_from_slice(score.as_bytes());
+            }
+        })
+    }
+
+    pub fn hashmap(key: &str, fields: &[(&str, &str)]) -> OwnedRecord {
+        build(ValueType::Hashmap, key, |v| {
+            v.extend(encode_length(fields.len() as u32));
+            for &(field, value) in fields {
+                push_string(v, field.as_bytes());
+                push_string(v, value.as_bytes());
+            }
+        })
+    }
+
+    /// `record` expiring at `expiry_ms` milliseconds since the epoch, replacing any expiry it had
+    pub fn with_expiry_ms(record: OwnedRecord, expiry_ms: u64) -> OwnedRecord {
+        let r = record.record();
+        let mut v = vec![0xfc];
+        v.extend((0..8).map(|i| (expiry_ms >> (8 * i)) as u8));
+        Record(r.0, r.1, None).ser(&mut v).expect("write into a Vec");
+        parse(&v)
+    }
+}
+
+
+/// test
+#[test]
+fn record_builder_test() {
+    use super::value::{ DecodedRecord, DecodedValue };
+
+    let decoded = |r: &OwnedRecord| DecodedRecord::decode(0, &r.record()).unwrap();
+    let b = |s: &str| s.as_bytes().to_vec();
+
+    let string = decoded(&RecordBuilder::string("k", "v"));
+    assert_eq!((string.key, string.value, string.expiry_ms), (b("k"), DecodedValue::String(b("v")), None));
+    let long = "x".repeat(100);
+    assert_eq!(decoded(&RecordBuilder::string("k", &long)).value, DecodedValue::String(b(&long)));
+
+    assert_eq!(decoded(&RecordBuilder::list("l", &["a", "b"])).value, DecodedValue::List(vec![b("a"), b("b")]));
+    assert_eq!(decoded(&RecordBuilder::set("s", &["a"])).value, DecodedValue::Set(vec![b("a")]));
+    assert_eq!(decoded(&RecordBuilder::sorted_set("z", &[("a", 1.5), ("b", -2.0)])).value,
+               DecodedValue::SortedSet(vec![(b("a"), 1.5), (b("b"), -2.0)]));
+    assert_eq!(decoded(&RecordBuilder::sorted_set("z", &[("a", 1e300), ("b", f64::MAX), ("c", f64::MIN)])).value,
+               DecodedValue::SortedSet(vec![(b("a"), 1e300), (b("b"), f64::MAX), (b("c"), f64::MIN)]));
+    assert_eq!(decoded(&RecordBuilder::hashmap("h", &[("f", "v")])).value, DecodedValue::Hash(vec![(b("f"), b("v"))]));
+
+    let expiring = RecordBuilder::with_expiry_ms(RecordBuilder::string("k", "v"), 1000);
+    assert_eq!(decoded(&expiring).expiry_ms, Some(1000));
+    assert_eq!(decoded(&RecordBuilder::with_expiry_ms(expiring, 2000)).expiry_ms, Some(2000));
+}
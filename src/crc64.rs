@@ -0,0 +1,91 @@
+use std::io::{ Write, Result };
+
+// reflected form of the Jones polynomial 0xad93d23594c935a9 used by Redis
+const POLY: u64 = 0x95ac9329ac4bc9b5;
+
+fn table() -> [u64; 256] {
+    let mut t = [0; 256];
+    for (i, e) in t.iter_mut().enumerate() {
+        let mut crc = i as u64;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 { (crc >> 1) ^ POLY } else { crc >> 1 };
+        }
+        *e = crc;
+    }
+    t
+}
+
+/// CRC64 checksum of RDB files and DUMP payloads
+pub struct Crc64 {
+    table: [u64; 256],
+    crc:   u64,
+}
+
+impl Crc64 {
+    pub fn new() -> Self {
+        Crc64 { table: table(), crc: 0 }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.crc = self.table[((self.crc ^ *b as u64) & 0xff) as usize] ^ (self.crc >> 8);
+        }
+    }
+
+    pub fn digest(&self) -> u64 {
+        self.crc
+    }
+}
+
+impl Default for Crc64 {
+    fn default() -> Self {
+        Crc64::new()
+    }
+}
+
+/// `Write` adapter which checksums everything written through it
+pub struct CrcWriter<W: Write> {
+    inner: W,
+    crc:   Crc64,
+}
+
+impl<W: Write> CrcWriter<W> {
+    pub fn new(inner: W) -> Self {
+        CrcWriter { inner, crc: Crc64::new() }
+    }
+
+    pub fn digest(&self) -> u64 {
+        self.crc.digest()
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for CrcWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.crc.update(&buf[..n]);
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+
+/// test
+#[test]
+fn crc64_test() {
+    let mut crc = Crc64::new();
+    crc.update(b"123456789");
+    assert_eq!(crc.digest(), 0xe9c6d914c4b8d9ca);
+
+    let mut w = CrcWriter::new(Vec::new());
+    assert!(w.write_all(b"12345").is_ok());
+    assert!(w.write_all(b"6789").is_ok());
+    assert_eq!(w.digest(), 0xe9c6d914c4b8d9ca);
+    assert_eq!(&w.into_inner()[..], b"123456789");
+}
@@ -0,0 +1,108 @@
+use std::io::{ Write, BufWriter, sink };
+
+use super::base64;
+use super::error::Result;
+use super::parser::{
+    RDBDec, RDBSer, Record, EncodedValue,
+    EncodedList, EncodedSet, EncodedSortedset, EncodedHashmap,
+};
+use super::parser::EncodedValue::*;
+
+/// columns of `RecordCsvWriter`
+pub const CSV_HEADER: &str = "db,key,key_encoding,type,elements,bytes,expiry_ms";
+
+/// write one CSV row of metadata per record, after a header: its database, its key, as text or,
+/// if it is not UTF-8 or has control characters, in base64, its value type, number of elements,
+/// serialized size in bytes, and expiry in milliseconds, empty if the key does not expire
+pub struct RecordCsvWriter<W: Write> {
+    out: BufWriter<W>,
+}
+
+/// CSV field, quoted if it has a comma, a quote or a line break
+pub fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+/// elements of `val`: 1 for a string, the entries of a list or a set, the members of a sorted set
+/// or the fields of a hash, and 0 for a module value
+pub fn element_count(val: &EncodedValue) -> Result<u64> {
+    let n = match val {
+        &V0(_)                                             => 1,
+        &V1(EncodedList(len, _)) | &V2(EncodedSet(len, _)) => u32::from(len) as usize,
+        &V3(EncodedSortedset(len, _))                      => u32::from(len) as usize,
+        &V4(EncodedHashmap(len, _))                        => u32::from(len) as usize,
+        &V7(_)                                             => 0,
+        &VA(ref z)                                         => Vec::<Vec<u8>>::decode(z)?.len(),
+        &VB(ref s)                                         => Vec::<i64>::decode(s)?.len(),
+        &VC(ref z)                                         => Vec::<(Vec<u8>, Vec<u8>)>::decode(z)?.len(),
+        &VD(ref z)                                         => Vec::<(Vec<u8>, Vec<u8>)>::decode(z)?.len(),
+    };
+    Ok(n as u64)
+}
+
+impl<W: Write> RecordCsvWriter<W> {
+    pub fn new(w: W) -> Result<Self> {
+        let mut out = BufWriter::new(w);
+        writeln!(out, "{}", CSV_HEADER)?;
+        Ok(RecordCsvWriter { out })
+    }
+
+    pub fn write_record(&mut self, db: u32, record: &Record) -> Result<()> {
+        let &Record(ref key, ref val, expiry) = record;
+        let key: Vec<u8> = RDBDec::decode(key)?;
+        let (key, key_encoding) = match String::from_utf8(key) {
+            Ok(s) if !s.contains(char::is_control) => (csv_field(&s), "text"),
+            Ok(s)                                  => (base64::encode(s.as_bytes()), "base64"),
+            Err(e)                                 => (base64::encode(e.as_bytes()), "base64"),
+        };
+        let expiry = match expiry {
+            Some(e) => e.checked_millis()?.to_string(),
+            None    => String::new(),
+        };
+        writeln!(self.out, "{},{},{},{},{},{},{}",
+                 db, key, key_encoding, val.value_type(), element_count(val)?, record.ser(&mut sink())?, expiry)?;
+        Ok(())
+    }
+
+    /// flush the output
+    pub fn finish(mut self) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+
+/// test
+#[test]
+fn record_csv_writer_test() {
+    use super::parser::{ record, parse_done };
+
+    assert_eq!(csv_field("user:1"), "user:1");
+    assert_eq!(csv_field("a,\"b\""), "\"a,\"\"b\"\"\"");
+
+    let mut w = RecordCsvWriter::new(Vec::new()).unwrap();
+    // k => v, expiring at 1000 ms
+    let string = parse_done(record(&[0xfc, 0xe8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x6b, 0x01, 0x76])).unwrap();
+    assert!(w.write_record(1, &string).is_ok());
+    // a,b => [1, 2]
+    let list = parse_done(record(&[0x01, 0x03, 0x61, 0x2c, 0x62, 0x02, 0x01, 0x31, 0x01, 0x32])).unwrap();
+    assert!(w.write_record(0, &list).is_ok());
+    // \xff\n => hash ziplist of ab => 12 and -2 => 300
+    let ziplist = parse_done(record(&[
+        0x0d, 0x02, 0xff, 0x0a, 0x18,
+        0x18, 0x00, 0x00, 0x00, 0x13, 0x00, 0x00, 0x00, 0x04, 0x00,
+        0x00, 0x02, 0x61, 0x62, 0x04, 0xfd, 0x02, 0xfe, 0xfe, 0x03, 0xc0, 0x2c, 0x01, 0xff,
+    ])).unwrap();
+    assert!(w.write_record(0, &ziplist).is_ok());
+    let out = w.out.into_inner().ok().unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), [
+        "db,key,key_encoding,type,elements,bytes,expiry_ms\n",
+        "1,k,text,string,1,14,1000\n",
+        "0,\"a,b\",text,list,2,10,\n",
+        "0,/wo=,base64,hashmap-ziplist,2,29,\n",
+    ].concat());
+}
@@ -0,0 +1,115 @@
+use std::collections::btree_map::{ BTreeMap, Entry };
+use std::fmt;
+
+use super::error::Result;
+use super::file::verify_checksum;
+use super::json::json_string;
+use super::parser::{ RDBSer, RDBDec, RDB, Record, parse_rdb };
+
+/// keys of two RDB files by how they differ, each as `DB:KEY` and sorted by database and key
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct DiffReport {
+    pub only_in_a: Vec<String>,
+    pub only_in_b: Vec<String>,
+    pub same:      Vec<String>,
+    pub different: Vec<String>,
+}
+
+impl DiffReport {
+    /// whether the files have the same keys with the same values
+    pub fn is_equivalent(&self) -> bool {
+        self.only_in_a.is_empty() && self.only_in_b.is_empty() && self.different.is_empty()
+    }
+
+    pub fn to_json(&self) -> String {
+        let list = |keys: &[String]| keys.iter().map(|k| json_string(k)).collect::<Vec<String>>().join(", ");
+        format!("{{\n  \"only_in_a\": [{}],\n  \"only_in_b\": [{}],\n  \"same\": [{}],\n  \"different\": [{}]\n}}\n",
+                list(&self.only_in_a), list(&self.only_in_b), list(&self.same), list(&self.different))
+    }
+}
+
+impl fmt::Display for DiffReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<40} {:>12}", "keys", "count")?;
+        writeln!(f, "{:<40} {:>12}", "only in a", self.only_in_a.len())?;
+        writeln!(f, "{:<40} {:>12}", "only in b", self.only_in_b.len())?;
+        writeln!(f, "{:<40} {:>12}", "same value", self.same.len())?;
+        writeln!(f, "{:<40} {:>12}", "different value", self.different.len())?;
+        for &(label, keys) in &[("<", &self.only_in_a), (">", &self.only_in_b), ("!", &self.different)] {
+            for key in keys.iter() {
+                writeln!(f, "{} {}", label, key)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+// value type and serialized value of each (database, key)
+type Values = BTreeMap<(u32, Vec<u8>), Vec<u8>>;
+
+/// value type and serialized value of each (database, key) of `r`, the first record winning for a duplicate key
+fn values(r: &RDB) -> Result<Values> {
+    let mut values = BTreeMap::new();
+    for db in r.databases() {
+        for &Record(ref key, ref val, _) in db.records() {
+            let key: Vec<u8> = RDBDec::decode(key)?;
+            if let Entry::Vacant(e) = values.entry((db.number(), key)) {
+                // the type too, as a list and a set of the same members serialize alike
+                let mut bytes = vec![val.value_type() as u8];
+                val.ser(&mut bytes)?;
+                e.insert(bytes);
+            }
+        }
+    }
+    Ok(values)
+}
+
+fn key_label(&(num, ref key): &(u32, Vec<u8>)) -> String {
+    format!("{}:{}", num, String::from_utf8_lossy(key))
+}
+
+/// compare the keys of the RDB files `a` and `b` and their values, by the bytes of the serialized
+/// value, with its type but without key and expiry, so that the same value in another encoding counts as different
+pub fn diff_rdbs(a: &[u8], b: &[u8]) -> Result<DiffReport> {
+    let ra = parse_rdb(a)?;
+    verify_checksum(a, ra.3)?;
+    let rb = parse_rdb(b)?;
+    verify_checksum(b, rb.3)?;
+    let (va, vb) = (values(&ra)?, values(&rb)?);
+
+    let mut report = DiffReport::default();
+    for (key, value) in &va {
+        match vb.get(key) {
+            Some(other) if other == value => report.same.push(key_label(key)),
+            Some(_)                       => report.different.push(key_label(key)),
+            None                          => report.only_in_a.push(key_label(key)),
+        }
+    }
+    report.only_in_b = vb.keys().filter(|k| !va.contains_key(*k)).map(key_label).collect();
+    Ok(report)
+}
+
+
+/// test
+#[test]
+fn diff_rdbs_test() {
+    let header = [0x52, 0x45, 0x44, 0x49, 0x53, 0x30, 0x30, 0x30, 0x36]; // REDIS0006
+    // <DatabaseNumber 0> a => 1, b => 2, c => 3
+    let a = [&header[..], &[0xfe, 0x00, 0x00, 0x01, 0x61, 0x01, 0x31, 0x00, 0x01, 0x62, 0x01, 0x32, 0x00, 0x01, 0x63, 0x01, 0x33, 0xff][..], &[0x00; 8][..]].concat();
+    // <DatabaseNumber 0> b => 2, c => 4, <DatabaseNumber 1> a => 1
+    let b = [&header[..], &[0xfe, 0x00, 0x00, 0x01, 0x62, 0x01, 0x32, 0x00, 0x01, 0x63, 0x01, 0x34, 0xfe, 0x01, 0x00, 0x01, 0x61, 0x01, 0x31, 0xff][..], &[0x00; 8][..]].concat();
+
+    let report = diff_rdbs(&a, &b).unwrap();
+    assert_eq!(report.only_in_a, vec!["0:a".to_string()]);
+    assert_eq!(report.only_in_b, vec!["1:a".to_string()]);
+    assert_eq!(report.same, vec!["0:b".to_string()]);
+    assert_eq!(report.different, vec!["0:c".to_string()]);
+    assert!(!report.is_equivalent());
+    assert!(diff_rdbs(&a, &a).unwrap().is_equivalent());
+    assert!(diff_rdbs(&a, &b[..10]).is_err());
+
+    // <DatabaseNumber 0> k => [a, b] against k => {a, b}
+    let list = [&header[..], &[0xfe, 0x00, 0x01, 0x01, 0x6b, 0x02, 0x01, 0x61, 0x01, 0x62, 0xff][..], &[0x00; 8][..]].concat();
+    let set = [&header[..], &[0xfe, 0x00, 0x02, 0x01, 0x6b, 0x02, 0x01, 0x61, 0x01, 0x62, 0xff][..], &[0x00; 8][..]].concat();
+    assert_eq!(diff_rdbs(&list, &set).unwrap().different, vec!["0:k".to_string()]);
+}
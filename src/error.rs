@@ -0,0 +1,115 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use nom::error::{ ErrorKind, VerboseError, VerboseErrorKind };
+
+use super::parser::UnknownValueType;
+
+#[derive(Debug)]
+pub enum RmergerError {
+    Parse(String),
+    ParseIncomplete,
+    ParseError(ErrorKind),
+    Lzf,
+    Io(io::Error),
+    InvalidArgument(String),
+    DuplicateKey(String),
+    DuplicateDatabase(u32),
+    ChecksumMismatch { expected: u64, actual: u64 },
+    UnsupportedValueType(u8),
+    UnsupportedVersion(u32),
+    InvalidExpiry,
+    // failed download of an input file from a URL
+    Http(String),
+    // lock file held by another process
+    Locked(String),
+    // error of an input file
+    Input(String, Box<RmergerError>),
+    // parse error at a byte offset of the input, with a hex dump of the bytes around it
+    ParseErrorAt { offset: usize, dump: String, error: Box<RmergerError> },
+}
+use self::RmergerError::*;
+
+pub type Result<T> = ::std::result::Result<T, RmergerError>;
+
+impl RmergerError {
+    /// attach the name of the input file the error comes from
+    pub fn in_file(self, name: &str) -> Self {
+        Input(name.to_string(), Box::new(self))
+    }
+}
+
+impl fmt::Display for RmergerError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &Parse(ref s)             => write!(f, "parse error: {}", s),
+            &ParseIncomplete          => write!(f, "unexpected end of file"),
+            &ParseError(ref e)        => write!(f, "parse error: {}", e.description()),
+            &Lzf                      => write!(f, "failed to decode LZF"),
+            &Io(ref e)                => write!(f, "{}", e),
+            &InvalidArgument(ref s)   => write!(f, "{}", s),
+            &DuplicateKey(ref k)      => write!(f, "duplicate key: {}", k),
+            &DuplicateDatabase(n)     => write!(f, "database {} is given more than once", n),
+            &ChecksumMismatch { expected, actual } =>
+                write!(f, "checksum mismatch: expected 0x{:016x}, actual 0x{:016x}", expected, actual),
+            &UnsupportedValueType(t)  => write!(f, "unsupported value type: 0x{:02x}", t),
+            &UnsupportedVersion(v)    => write!(f, "unsupported RDB version: {}", v),
+            &InvalidExpiry            => write!(f, "invalid expiry time"),
+            &Http(ref e)              => write!(f, "download failed: {}", e),
+            &Locked(ref path)         => write!(f, "{} is locked", path),
+            &Input(ref name, ref e)   => write!(f, "{}: {}", name, e),
+            &ParseErrorAt { offset, ref dump, ref error } =>
+                write!(f, "{} at offset {} (0x{:x}): {}", error, offset, offset, dump),
+        }
+    }
+}
+
+impl Error for RmergerError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            &Io(ref e)       => Some(e),
+            &Input(_, ref e) => Some(&**e),
+            &ParseErrorAt { ref error, .. } => Some(&**error),
+            _                => None,
+        }
+    }
+}
+
+impl From<io::Error> for RmergerError {
+    fn from(e: io::Error) -> Self {
+        Io(e)
+    }
+}
+
+impl From<ErrorKind> for RmergerError {
+    fn from(e: ErrorKind) -> Self {
+        ParseError(e)
+    }
+}
+
+impl From<UnknownValueType> for RmergerError {
+    fn from(e: UnknownValueType) -> Self {
+        UnsupportedValueType(e.0)
+    }
+}
+
+/// the error of the innermost parser which failed
+impl<'a> From<VerboseError<&'a [u8]>> for RmergerError {
+    fn from(e: VerboseError<&'a [u8]>) -> Self {
+        match e.errors.first() {
+            Some(&(_, VerboseErrorKind::Nom(kind)))  => ParseError(kind),
+            Some(&(_, VerboseErrorKind::Char(c)))    => Parse(format!("expected '{}'", c)),
+            Some(&(_, VerboseErrorKind::Context(s))) => Parse(s.to_string()),
+            None                                     => ParseError(ErrorKind::Fail),
+        }
+    }
+}
+
+impl From<RmergerError> for io::Error {
+    fn from(e: RmergerError) -> Self {
+        match e {
+            Io(e) => e,
+            e     => io::Error::new(io::ErrorKind::InvalidData, e.to_string()),
+        }
+    }
+}
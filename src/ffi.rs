@@ -0,0 +1,150 @@
+use std::collections::BTreeMap;
+use std::os::raw::c_char;
+use std::ptr;
+use std::slice;
+
+use super::parser::{ RDBDec, OwnedRecord, Database, DatabaseNumber, parse_rdb };
+
+/// value type returned by `rmerger_get_value_type` for a record which does not exist
+pub const RMERGER_NO_VALUE_TYPE: u8 = 0xff;
+
+/// parsed RDB file handed to C code, freed with `rmerger_free`
+pub struct OpaqueRDB {
+    // records of each database, and their keys followed by a NUL byte
+    databases: BTreeMap<u32, Vec<(OwnedRecord, Vec<u8>)>>,
+}
+
+impl OpaqueRDB {
+    fn record(&self, db: u32, idx: usize) -> Option<&(OwnedRecord, Vec<u8>)> {
+        self.databases.get(&db).and_then(|r| r.get(idx))
+    }
+}
+
+/// parse the RDB file of `len` bytes at `data`, or NULL if it does not parse
+///
+/// # Safety
+///
+/// `data` must point to `len` readable bytes; they are copied and need not outlive the call
+#[no_mangle]
+pub unsafe extern "C" fn rmerger_parse(data: *const u8, len: usize) -> *mut OpaqueRDB {
+    if data.is_null() {
+        return ptr::null_mut();
+    }
+    let input = slice::from_raw_parts(data, len);
+    let rdb = match parse_rdb(input) {
+        Ok(rdb) => rdb,
+        Err(e)  => {
+            warn!("rmerger_parse: {}", e);
+            return ptr::null_mut();
+        },
+    };
+    let mut databases: BTreeMap<u32, Vec<(OwnedRecord, Vec<u8>)>> = BTreeMap::new();
+    for &Database(DatabaseNumber(_, num), ref records) in rdb.databases() {
+        let db = databases.entry(num).or_default();
+        for r in records {
+            let mut key: Vec<u8> = match RDBDec::decode(&r.0) {
+                Ok(key) => key,
+                Err(e)  => {
+                    warn!("rmerger_parse: {}", e);
+                    return ptr::null_mut();
+                },
+            };
+            key.push(0);
+            db.push((r.to_owned(), key));
+        }
+    }
+    Box::into_raw(Box::new(OpaqueRDB { databases }))
+}
+
+/// free an RDB returned by `rmerger_parse`; NULL is ignored
+///
+/// # Safety
+///
+/// `rdb` must be NULL or returned by `rmerger_parse` and not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn rmerger_free(rdb: *mut OpaqueRDB) {
+    if !rdb.is_null() {
+        drop(Box::from_raw(rdb));
+    }
+}
+
+/// number of records of database `db`, 0 if there is no such database
+///
+/// # Safety
+///
+/// `rdb` must be NULL or returned by `rmerger_parse` and not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn rmerger_record_count(rdb: *const OpaqueRDB, db: u32) -> usize {
+    rdb.as_ref().and_then(|rdb| rdb.databases.get(&db)).map_or(0, |r| r.len())
+}
+
+/// key of record `idx` of database `db`, followed by a NUL byte, or NULL if there is no such
+/// record; the string is owned by `rdb`, and a key with NUL bytes is read up to
+/// `rmerger_get_key_len`
+///
+/// # Safety
+///
+/// `rdb` must be NULL or returned by `rmerger_parse` and not freed yet; the string is valid until
+/// `rdb` is freed
+#[no_mangle]
+pub unsafe extern "C" fn rmerger_get_key(rdb: *const OpaqueRDB, db: u32, idx: usize) -> *const c_char {
+    rdb.as_ref().and_then(|rdb| rdb.record(db, idx)).map_or(ptr::null(), |&(_, ref key)| key.as_ptr() as *const c_char)
+}
+
+/// length in bytes of the key of record `idx` of database `db`, NUL bytes included and the
+/// terminating one excluded, or 0 if there is no such record
+///
+/// # Safety
+///
+/// `rdb` must be NULL or returned by `rmerger_parse` and not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn rmerger_get_key_len(rdb: *const OpaqueRDB, db: u32, idx: usize) -> usize {
+    rdb.as_ref().and_then(|rdb| rdb.record(db, idx)).map_or(0, |&(_, ref key)| key.len() - 1)
+}
+
+/// value type byte of record `idx` of database `db`, as in the RDB file, or
+/// `RMERGER_NO_VALUE_TYPE` if there is no such record
+///
+/// # Safety
+///
+/// `rdb` must be NULL or returned by `rmerger_parse` and not freed yet
+#[no_mangle]
+pub unsafe extern "C" fn rmerger_get_value_type(rdb: *const OpaqueRDB, db: u32, idx: usize) -> u8 {
+    rdb.as_ref().and_then(|rdb| rdb.record(db, idx))
+        .map_or(RMERGER_NO_VALUE_TYPE, |&(ref r, _)| r.record().1.value_type() as u8)
+}
+
+
+/// test
+#[test]
+fn ffi_test() {
+    use std::ffi::CStr;
+    use super::parser::TEST_RDB_A;
+
+    let file = TEST_RDB_A;
+    // b\0c => 2 in database 0
+    let nul_key = b"REDIS0006\xfe\x00\x00\x03b\x00c\x012\xff\x00\x00\x00\x00\x00\x00\x00\x00";
+    unsafe {
+        let rdb = rmerger_parse(file.as_ptr(), file.len());
+        assert!(!rdb.is_null());
+        assert_eq!((rmerger_record_count(rdb, 0), rmerger_record_count(rdb, 1), rmerger_record_count(rdb, 2)), (2, 0, 1));
+        assert_eq!(CStr::from_ptr(rmerger_get_key(rdb, 0, 1)).to_bytes(), b"l");
+        assert_eq!(CStr::from_ptr(rmerger_get_key(rdb, 2, 0)).to_bytes(), b"b");
+        assert_eq!(rmerger_get_key_len(rdb, 2, 0), 1);
+        assert_eq!(rmerger_get_key_len(rdb, 0, 2), 0);
+        assert_eq!((rmerger_get_value_type(rdb, 0, 0), rmerger_get_value_type(rdb, 0, 1)), (0x00, 0x01));
+        assert!(rmerger_get_key(rdb, 0, 2).is_null());
+        assert_eq!(rmerger_get_value_type(rdb, 1, 0), RMERGER_NO_VALUE_TYPE);
+        rmerger_free(rdb);
+
+        // a key with a NUL byte is read whole by its length
+        let rdb = rmerger_parse(nul_key.as_ptr(), nul_key.len());
+        assert_eq!(rmerger_get_key_len(rdb, 0, 0), 3);
+        assert_eq!(slice::from_raw_parts(rmerger_get_key(rdb, 0, 0) as *const u8, 4), b"b\x00c\x00");
+        rmerger_free(rdb);
+
+        assert!(rmerger_parse(file.as_ptr(), 12).is_null());
+        assert_eq!(rmerger_record_count(ptr::null(), 0), 0);
+        rmerger_free(ptr::null_mut());
+    }
+}
@@ -0,0 +1,81 @@
+/// glob pattern of Redis `SCAN MATCH`: `*`, `?`, `[...]` (with `^` and ranges) and `\` escapes
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Pattern(Vec<char>);
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Self {
+        Pattern(pattern.chars().collect())
+    }
+
+    pub fn matches(&self, s: &str) -> bool {
+        let s: Vec<char> = s.chars().collect();
+        glob_match(&self.0, &s)
+    }
+}
+
+/// match `c` against the class starting after `[`, returning the result and the rest of the pattern
+fn class_match(p: &[char], c: char) -> (bool, &[char]) {
+    let (negate, mut p) = match p.first() {
+        Some(&'^') => (true, &p[1..]),
+        _          => (false, p),
+    };
+    let mut matched = false;
+    loop {
+        match p {
+            [] => break,
+            [']', rest @ ..] => {
+                p = rest;
+                break;
+            },
+            ['\\', x, rest @ ..] => {
+                matched |= *x == c;
+                p = rest;
+            },
+            [a, '-', b, rest @ ..] if *b != ']' => {
+                let (lo, hi) = if a <= b { (*a, *b) } else { (*b, *a) };
+                matched |= lo <= c && c <= hi;
+                p = rest;
+            },
+            [x, rest @ ..] => {
+                matched |= *x == c;
+                p = rest;
+            },
+        }
+    }
+    (matched != negate, p)
+}
+
+fn glob_match(p: &[char], s: &[char]) -> bool {
+    match p {
+        [] => s.is_empty(),
+        ['*', rest @ ..] => (0..s.len() + 1).any(|i| glob_match(rest, &s[i..])),
+        _ if s.is_empty() => false,
+        ['?', rest @ ..] => glob_match(rest, &s[1..]),
+        ['[', rest @ ..] => {
+            let (matched, rest) = class_match(rest, s[0]);
+            matched && glob_match(rest, &s[1..])
+        },
+        ['\\', x, rest @ ..] => *x == s[0] && glob_match(rest, &s[1..]),
+        [x, rest @ ..] => *x == s[0] && glob_match(rest, &s[1..]),
+    }
+}
+
+
+/// test
+#[test]
+fn pattern_test() {
+    assert!(Pattern::new("user:*").matches("user:1"));
+    assert!(Pattern::new("user:*").matches("user:"));
+    assert!(!Pattern::new("user:*").matches("session:1"));
+    assert!(Pattern::new("h?llo").matches("hello"));
+    assert!(!Pattern::new("h?llo").matches("hllo"));
+    assert!(Pattern::new("h[ae]llo").matches("hallo"));
+    assert!(!Pattern::new("h[ae]llo").matches("hillo"));
+    assert!(Pattern::new("h[^e]llo").matches("hallo"));
+    assert!(!Pattern::new("h[^e]llo").matches("hello"));
+    assert!(Pattern::new("h[a-b]llo").matches("hbllo"));
+    assert!(Pattern::new("*a*b*").matches("xxaxxbxx"));
+    assert!(Pattern::new("a\\*").matches("a*"));
+    assert!(!Pattern::new("a\\*").matches("ab"));
+    assert!(Pattern::new("*").matches("\u{fffd}\u{fffd}"));
+}
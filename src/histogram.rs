@@ -0,0 +1,159 @@
+use std::io::{ Write, Result };
+use std::fmt;
+
+// bucket i > 0 holds sizes in [2^(i+5), 2^(i+6)), the last one everything from 1 MiB
+const SIZE_BUCKETS: usize = 16;
+
+/// `Write` adapter which counts the bytes written through it
+pub struct CountingWriter<W: Write> {
+    inner: W,
+    count: u64,
+}
+
+impl<W: Write> CountingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        CountingWriter { inner, count: 0 }
+    }
+
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+}
+
+impl<W: Write> Write for CountingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.count += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// number of records by serialized value size, in power-of-two buckets from 0-63 bytes up to 1 MiB and more
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct SizeHistogram {
+    counts: [u64; SIZE_BUCKETS],
+    max:    u64,
+}
+
+fn bucket(size: u64) -> usize {
+    if size < 64 {
+        return 0;
+    }
+    let log2 = 63 - size.leading_zeros() as usize;
+    (log2 - 5).min(SIZE_BUCKETS - 1)
+}
+
+/// smallest size of bucket `i`
+fn bucket_start(i: usize) -> u64 {
+    if i == 0 { 0 } else { 1 << (i + 5) }
+}
+
+impl SizeHistogram {
+    pub fn add(&mut self, size: u64) {
+        self.counts[bucket(size)] += 1;
+        self.max = self.max.max(size);
+    }
+
+    /// add the counts of `other`
+    pub fn absorb(&mut self, other: &SizeHistogram) {
+        for (n, m) in self.counts.iter_mut().zip(other.counts.iter()) {
+            *n += m;
+        }
+        self.max = self.max.max(other.max);
+    }
+
+    pub fn total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    /// (smallest size, count) of each bucket
+    pub fn buckets(&self) -> Vec<(u64, u64)> {
+        self.counts.iter().enumerate().map(|(i, n)| (bucket_start(i), *n)).collect()
+    }
+
+    /// upper bound of the `p`-th percentile (0 < p <= 100): the largest size of the bucket
+    /// holding it, or the largest size seen for the last bucket
+    pub fn percentile(&self, p: f64) -> Option<u64> {
+        let total = self.total();
+        if total == 0 {
+            return None;
+        }
+        let rank = ((p / 100.0) * total as f64).ceil().max(1.0) as u64;
+        let mut seen = 0;
+        for (i, n) in self.counts.iter().enumerate() {
+            seen += n;
+            if seen >= rank {
+                let end = if i + 1 < SIZE_BUCKETS { bucket_start(i + 1) - 1 } else { self.max };
+                return Some(end.min(self.max));
+            }
+        }
+        Some(self.max)
+    }
+}
+
+impl fmt::Display for SizeHistogram {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "{:<28} {:>12}", "value bytes", "records")?;
+        for (i, n) in self.counts.iter().enumerate() {
+            let range = if i + 1 < SIZE_BUCKETS {
+                format!("{}-{}", bucket_start(i), bucket_start(i + 1) - 1)
+            } else {
+                format!(">= {}", bucket_start(i))
+            };
+            writeln!(f, "{:<28} {:>12}", range, n)?;
+        }
+        for &(name, p) in &[("p50", 50.0), ("p90", 90.0), ("p99", 99.0), ("p99.9", 99.9)] {
+            match self.percentile(p) {
+                Some(size) => writeln!(f, "{:<28} {:>12}", name, format!("<= {}", size))?,
+                None       => writeln!(f, "{:<28} {:>12}", name, "-")?,
+            }
+        }
+        Ok(())
+    }
+}
+
+
+/// test
+#[test]
+fn size_histogram_test() {
+    assert_eq!(bucket(0), 0);
+    assert_eq!(bucket(63), 0);
+    assert_eq!(bucket(64), 1);
+    assert_eq!(bucket(127), 1);
+    assert_eq!(bucket(128), 2);
+    assert_eq!(bucket((1 << 20) - 1), 14);
+    assert_eq!(bucket(1 << 20), 15);
+    assert_eq!(bucket(u64::MAX), 15);
+
+    let mut h = SizeHistogram::default();
+    assert_eq!(h.percentile(50.0), None);
+    for _ in 0..90 {
+        h.add(10);
+    }
+    for _ in 0..9 {
+        h.add(100);
+    }
+    h.add(2 << 20);
+    assert_eq!(h.total(), 100);
+    assert_eq!(h.percentile(50.0), Some(63));
+    assert_eq!(h.percentile(90.0), Some(63));
+    assert_eq!(h.percentile(99.0), Some(127));
+    assert_eq!(h.percentile(99.9), Some(2 << 20));
+}
+
+#[test]
+fn counting_writer_test() {
+    let mut w = CountingWriter::new(Vec::new());
+    assert!(w.write_all(b"abc").is_ok());
+    assert!(w.write_all(b"de").is_ok());
+    assert_eq!(w.count(), 5);
+    assert_eq!(w.into_inner(), b"abcde".to_vec());
+}
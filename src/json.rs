@@ -0,0 +1,190 @@
+use std::io::{ Write, BufWriter };
+
+use super::base64;
+use super::error::Result;
+use super::parser::{
+    RDBDec, Record, EncodedString,
+    EncodedList, EncodedSet, EncodedSortedset, EncodedHashmap,
+};
+use super::parser::EncodedValue::*;
+
+/// write records as a pretty-printed JSON array
+pub struct RecordJsonWriter<W: Write> {
+    out:   BufWriter<W>,
+    first: bool,
+}
+
+/// JSON string literal, escaping quotes, backslashes and control characters
+pub fn json_string(s: &str) -> String {
+    let mut out = String::with_capacity(s.len() + 2);
+    out.push('"');
+    for c in s.chars() {
+        match c {
+            '"'  => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+fn decoded(s: &EncodedString) -> Result<String> {
+    Ok(json_string(&String::decode(s)?))
+}
+
+fn text(bytes: &[u8]) -> String {
+    json_string(&String::from_utf8_lossy(bytes))
+}
+
+fn array(items: Vec<String>) -> String {
+    if items.is_empty() {
+        return "[]".to_string();
+    }
+    format!("[\n      {}\n    ]", items.join(",\n      "))
+}
+
+/// members of a sorted set with their scores, a number unless it is infinite or NaN
+fn sorted_set(members: Vec<(Vec<u8>, Vec<u8>)>) -> String {
+    array(members.iter().map(|&(ref member, ref score)| {
+        let score = String::from_utf8_lossy(score);
+        let score = match score.parse::<f64>() {
+            Ok(f) if f.is_finite() => score.to_string(),
+            _ => json_string(&score),
+        };
+        format!("{{ \"member\": {}, \"score\": {} }}", text(member), score)
+    }).collect())
+}
+
+fn hash(fields: Vec<(Vec<u8>, Vec<u8>)>) -> String {
+    if fields.is_empty() {
+        return "{}".to_string();
+    }
+    let items: Vec<String> = fields.iter().map(|&(ref field, ref value)| format!("{}: {}", text(field), text(value))).collect();
+    format!("{{\n      {}\n    }}", items.join(",\n      "))
+}
+
+/// base64 blob for module values, which are not decoded
+fn opaque_bytes(bytes: &[u8], encoding: &str) -> String {
+    format!("{{ \"base64\": {}, \"note\": {} }}",
+            json_string(&base64::encode(bytes)),
+            json_string(&format!("{} encoding is not expanded", encoding)))
+}
+
+impl<W: Write> RecordJsonWriter<W> {
+    pub fn new(w: W) -> Result<Self> {
+        let mut out = BufWriter::new(w);
+        out.write_all(b"[")?;
+        Ok(RecordJsonWriter { out, first: true })
+    }
+
+    pub fn write_record(&mut self, db: u32, record: &Record) -> Result<()> {
+        let &Record(ref key, ref val, expiry) = record;
+        let (type_name, value) = match val {
+            &V0(ref s) => ("string", decoded(s)?),
+            &V1(EncodedList(_, ref v)) | &V2(EncodedSet(_, ref v)) => {
+                let name = if let &V1(_) = val { "list" } else { "set" };
+                (name, array(v.iter().map(decoded).collect::<Result<_>>()?))
+            },
+            &V3(EncodedSortedset(_, ref v)) => {
+                let members = v.iter().map(|&(ref member, _, score)| Ok((RDBDec::decode(member)?, score.to_vec()))).collect::<Result<_>>()?;
+                ("zset", sorted_set(members))
+            },
+            &V4(EncodedHashmap(_, ref v)) => {
+                let fields = v.iter().map(|&(ref field, ref value)| Ok((RDBDec::decode(field)?, RDBDec::decode(value)?))).collect::<Result<_>>()?;
+                ("hash", hash(fields))
+            },
+            &V7(ref m) => ("module", opaque_bytes(m.1, &format!("module {}", m.name()))),
+            &VA(ref z) => {
+                let items: Vec<Vec<u8>> = RDBDec::decode(z)?;
+                ("list", array(items.iter().map(|e| text(e)).collect()))
+            },
+            &VB(ref i) => {
+                let members: Vec<i64> = RDBDec::decode(i)?;
+                ("set", array(members.iter().map(|n| json_string(&n.to_string())).collect()))
+            },
+            &VC(ref z) => ("zset", sorted_set(RDBDec::decode(z)?)),
+            &VD(ref z) => ("hash", hash(RDBDec::decode(z)?)),
+        };
+        let expiry = match expiry {
+            Some(e) => e.checked_millis()?.to_string(),
+            None    => "null".to_string(),
+        };
+
+        let separator = if self.first { "\n" } else { ",\n" };
+        self.first = false;
+        write!(self.out, "{}  {{\n    \"db\": {},\n    \"key\": {},\n    \"type\": {},\n    \"value\": {},\n    \"expiry_ms\": {}\n  }}",
+               separator, db, decoded(key)?, json_string(type_name), value, expiry)?;
+        Ok(())
+    }
+
+    /// close the array and flush the output
+    pub fn finish(mut self) -> Result<()> {
+        self.out.write_all(b"\n]\n")?;
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+
+/// test
+#[test]
+fn json_string_test() {
+    assert_eq!(json_string("a\"b\\c\n\u{1}"), "\"a\\\"b\\\\c\\n\\u0001\"");
+}
+
+#[test]
+fn record_json_writer_test() {
+    use super::parser::record;
+
+    let case_1 = [0xfc, 0xe8, 0x03, 0, 0, 0, 0, 0, 0, 0x00, 0x01, 0x61, 0x02, 0x22, 0x31]; // a => "1 expiring at 1000 ms
+    let case_2 = [0x01, 0x01, 0x6c, 0x02, 0x01, 0x78, 0xc0, 0x07]; // l => [x, 7]
+    let mut out = Vec::new();
+    {
+        let mut w = RecordJsonWriter::new(&mut out).unwrap();
+        for &(db, bytes) in &[(0, &case_1[..]), (3, &case_2[..])] {
+            let r = match record(bytes) {
+                Ok((_, r)) => r,
+                _ => panic!(),
+            };
+            assert!(w.write_record(db, &r).is_ok());
+        }
+        assert!(w.finish().is_ok());
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), concat!(
+        "[\n",
+        "  {\n    \"db\": 0,\n    \"key\": \"a\",\n    \"type\": \"string\",\n    \"value\": \"\\\"1\",\n    \"expiry_ms\": 1000\n  },\n",
+        "  {\n    \"db\": 3,\n    \"key\": \"l\",\n    \"type\": \"list\",\n    \"value\": [\n      \"x\",\n      \"7\"\n    ],\n    \"expiry_ms\": null\n  }\n",
+        "]\n"));
+
+    let mut empty = Vec::new();
+    assert!(RecordJsonWriter::new(&mut empty).unwrap().finish().is_ok());
+    assert_eq!(empty, b"[\n]\n");
+}
+
+#[test]
+fn record_json_writer_ziplist_test() {
+    use super::parser::{ record, ziplist_encode };
+
+    let zl = ziplist_encode(&[b"a", b"1.5"]);
+    let mut bytes = vec![0x0c, 0x01, 0x7a, zl.len() as u8]; // z => { a: 1.5 } as a ziplist
+    bytes.extend_from_slice(&zl);
+    let mut out = Vec::new();
+    {
+        let mut w = RecordJsonWriter::new(&mut out).unwrap();
+        let r = match record(&bytes) {
+            Ok((_, r)) => r,
+            _ => panic!(),
+        };
+        assert!(w.write_record(0, &r).is_ok());
+        assert!(w.finish().is_ok());
+    }
+    assert_eq!(String::from_utf8(out).unwrap(), concat!(
+        "[\n",
+        "  {\n    \"db\": 0,\n    \"key\": \"z\",\n    \"type\": \"zset\",\n    \"value\": [\n      { \"member\": \"a\", \"score\": 1.5 }\n    ],\n    \"expiry_ms\": null\n  }\n",
+        "]\n"));
+}
@@ -1,6 +1,19 @@
-#[macro_use] extern crate nom;
-#[macro_use] extern crate bitflags;
-extern crate nix;
+#![deny(deprecated)]
+
+extern crate nom;
+#[macro_use] extern crate log;
+extern crate rand;
+extern crate flate2;
+extern crate rustc_hash;
+#[cfg(not(target_arch = "wasm32"))] extern crate memmap2;
+#[cfg(feature = "serde")] extern crate serde;
+#[cfg(feature = "serde")] extern crate serde_json;
+#[cfg(feature = "tracing")] extern crate tracing;
+#[cfg(feature = "tracing")] extern crate tracing_subscriber;
+#[cfg(feature = "python")] extern crate pyo3;
+#[cfg(feature = "wasm")] extern crate wasm_bindgen;
+#[cfg(feature = "wasm")] extern crate js_sys;
+#[cfg(feature = "http")] extern crate ureq;
 
 macro_rules! assert_result {
     ( $expr: expr, $err: expr ) => {
@@ -8,5 +21,26 @@ macro_rules! assert_result {
     };
 }
 
+pub mod error;
 pub mod parser;
-pub mod file;
+// file system, memory mapping and sockets, which wasm32-unknown-unknown does not have
+#[cfg(not(target_arch = "wasm32"))] pub mod file;
+pub mod stream;
+pub mod base64;
+pub mod json;
+pub mod csv;
+pub mod crc64;
+pub mod restore;
+pub mod resp;
+#[cfg(not(target_arch = "wasm32"))] pub mod replay;
+pub mod builder;
+pub mod histogram;
+pub mod glob;
+pub mod bloom;
+#[cfg(not(target_arch = "wasm32"))] pub mod diff;
+pub mod value;
+#[cfg(feature = "serde")] mod serialize;
+pub mod logger;
+#[cfg(feature = "ffi")] pub mod ffi;
+#[cfg(feature = "python")] pub mod python;
+#[cfg(feature = "wasm")] pub mod wasm;
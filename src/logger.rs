@@ -0,0 +1,170 @@
+use log::{ self, Log, Level, LevelFilter, Metadata, Record, SetLoggerError };
+use std::time::{ SystemTime, UNIX_EPOCH };
+use super::json::json_string;
+use super::parser::civil_date;
+#[cfg(feature = "tracing")] use std::error::Error;
+#[cfg(feature = "tracing")] use std::io;
+#[cfg(feature = "tracing")] use tracing_subscriber::filter::LevelFilter as TracingLevelFilter;
+#[cfg(feature = "tracing")] use tracing_subscriber::fmt::writer::{ BoxMakeWriter, MakeWriterExt };
+
+/// `TIME [LEVEL target] message` lines, warnings and errors on stderr and the rest on stdout
+pub struct StdLogger {
+    level:       LevelFilter,
+    stderr_only: bool,
+    timestamps:  bool,
+    format:      LogFormat,
+}
+
+/// format of the lines written by `StdLogger` and `init_tracing`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum LogFormat {
+    Text,
+    /// one JSON object per line, for log aggregation
+    Json,
+}
+
+/// `t` in RFC 3339, UTC to the second, e.g. `2024-01-01T00:00:00Z`
+pub fn rfc3339(t: SystemTime) -> String {
+    let secs = t.duration_since(UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+    let (year, month, day) = civil_date(secs / 86400);
+    let secs = secs % 86400;
+    format!("{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z", year, month, day, secs / 3600, secs / 60 % 60, secs % 60)
+}
+
+impl StdLogger {
+    pub fn new(level: LevelFilter) -> Self {
+        StdLogger { level, stderr_only: false, timestamps: true, format: LogFormat::Text }
+    }
+
+    /// write every message to stderr, for when stdout carries the output
+    pub fn stderr_only(mut self, stderr_only: bool) -> Self {
+        self.stderr_only = stderr_only;
+        self
+    }
+
+    /// prefix every line with the time (the default), or not, e.g. when the lines go into
+    /// another logging system which adds its own
+    pub fn timestamps(mut self, timestamps: bool) -> Self {
+        self.timestamps = timestamps;
+        self
+    }
+
+    /// write `{"level":..,"target":..,"msg":..,"ts":..}` lines, all of them to stdout unless
+    /// `stderr_only`, so that a log collector reads a single stream
+    pub fn format(mut self, format: LogFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// install the logger
+    pub fn install(self) -> Result<(), SetLoggerError> {
+        let level = self.level;
+        log::set_boxed_logger(Box::new(self))?;
+        log::set_max_level(level);
+        Ok(())
+    }
+
+    /// install the logger for messages up to `level` (info if `None`)
+    pub fn init(level: Option<LevelFilter>) -> Result<(), SetLoggerError> {
+        StdLogger::init_with_format(level, LogFormat::Text)
+    }
+
+    /// install the logger for messages up to `level` (info if `None`), writing `format` lines
+    pub fn init_with_format(level: Option<LevelFilter>, format: LogFormat) -> Result<(), SetLoggerError> {
+        StdLogger::new(level.unwrap_or(LevelFilter::Info)).format(format).install()
+    }
+
+    /// the line written for `record`
+    fn line(&self, record: &Record) -> String {
+        match self.format {
+            LogFormat::Text => {
+                let time = if self.timestamps { rfc3339(SystemTime::now()) + " " } else { String::new() };
+                format!("{}[{} {}] {}", time, record.level(), record.target(), record.args())
+            },
+            LogFormat::Json => {
+                let time = if self.timestamps {
+                    format!(",\"ts\":{}", json_string(&rfc3339(SystemTime::now())))
+                } else {
+                    String::new()
+                };
+                format!("{{\"level\":{},\"target\":{},\"msg\":{}{}}}",
+                        json_string(record.level().as_str()), json_string(record.target()),
+                        json_string(&record.args().to_string()), time)
+            },
+        }
+    }
+}
+
+impl Log for StdLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.level
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = self.line(record);
+        if self.stderr_only || (self.format == LogFormat::Text && record.level() <= Level::Warn) {
+            eprintln!("{}", line);
+        } else {
+            println!("{}", line);
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+/// install a tracing subscriber instead of `StdLogger` for events up to `level`, which also
+/// receives the messages of the `log` macros; warnings and errors go to stderr and the rest
+/// to stdout, unless `stderr_only`
+#[cfg(feature = "tracing")]
+pub fn init_tracing(level: LevelFilter, format: LogFormat, stderr_only: bool) -> Result<(), Box<dyn Error + Send + Sync>> {
+    let max_level = match level {
+        LevelFilter::Off   => TracingLevelFilter::OFF,
+        LevelFilter::Error => TracingLevelFilter::ERROR,
+        LevelFilter::Warn  => TracingLevelFilter::WARN,
+        LevelFilter::Info  => TracingLevelFilter::INFO,
+        LevelFilter::Debug => TracingLevelFilter::DEBUG,
+        LevelFilter::Trace => TracingLevelFilter::TRACE,
+    };
+    let writer = if stderr_only {
+        BoxMakeWriter::new(io::stderr)
+    } else {
+        BoxMakeWriter::new(io::stderr.with_max_level(tracing::Level::WARN).or_else(io::stdout))
+    };
+    let builder = tracing_subscriber::fmt().with_max_level(max_level).with_writer(writer);
+    match format {
+        LogFormat::Text => builder.try_init(),
+        LogFormat::Json => builder.json().try_init(),
+    }
+}
+
+
+/// test
+#[test]
+fn rfc3339_test() {
+    use std::time::Duration;
+
+    assert_eq!(rfc3339(UNIX_EPOCH), "1970-01-01T00:00:00Z");
+    assert_eq!(rfc3339(UNIX_EPOCH + Duration::from_secs(951782400 + 3723)), "2000-02-29T01:02:03Z");
+    assert_eq!(rfc3339(UNIX_EPOCH + Duration::from_secs(1704067199)), "2023-12-31T23:59:59Z");
+}
+
+#[test]
+fn json_line_test() {
+    let logger = StdLogger::new(LevelFilter::Info).timestamps(false).format(LogFormat::Json);
+    // the arguments only live until the end of the statement
+    let line = logger.line(&Record::builder()
+        .level(Level::Warn)
+        .target("rmerger::file")
+        .args(format_args!("key \"{}\" skipped", "a\tb"))
+        .build());
+    assert_eq!(line, r#"{"level":"WARN","target":"rmerger::file","msg":"key \"a\tb\" skipped"}"#);
+
+    let logger = StdLogger::new(LevelFilter::Info).format(LogFormat::Json);
+    let record = Record::builder().level(Level::Info).target("rmerger").args(format_args!("done")).build();
+    let line = logger.line(&record);
+    assert!(line.starts_with(r#"{"level":"INFO","target":"rmerger","msg":"done","ts":""#));
+    assert!(line.ends_with(r#"Z"}"#));
+}
@@ -1,87 +1,1219 @@
-extern crate nom;
+#![deny(deprecated)]
+
 extern crate rmerger;
-extern crate getopts;
+extern crate clap;
+extern crate clap_complete;
+#[macro_use] extern crate log;
+
+use rmerger::file::{ memory_map_read, read_input_file, read_stdin, read_key_list, read_list_lines, verify_checksum, validate_file, acquire_lock, STDIN_NAME, STDOUT_NAME, DEFAULT_SEED, estimate_output_size_with, DEFAULT_OUTPUT_OVERHEAD, estimate_disk_usage, DEFAULT_BLOOM_ERROR_RATE, value_types, is_url, redact_url, TempOutput, PartRDB, SplitRDB, DiffRDB, ConflictStrategy, MergeStrategy, DedupKey, ZiplistLimits, Progress, ProgressCallback };
+#[cfg(feature = "http")] use rmerger::file::download;
+use rmerger::parser::{ parse_rdb, parse_rdb_skipping, RDB, MIN_RDB_VERSION, MAX_RDB_VERSION };
+use rmerger::error::{ self, RmergerError };
+use rmerger::json::RecordJsonWriter;
+use rmerger::csv::RecordCsvWriter;
+use rmerger::glob::Pattern;
+use rmerger::restore::RestoreCommandWriter;
+use rmerger::resp::{ RespCommandWriter, TextCommandWriter };
+use rmerger::replay::{ ReplayTarget, RedisReplayer };
+use rmerger::logger::LogFormat;
+#[cfg(not(feature = "tracing"))] use rmerger::logger::StdLogger;
+#[cfg(feature = "tracing")] use rmerger::logger::init_tracing;
+use rmerger::diff::diff_rdbs;
+#[cfg(feature = "serde")] use rmerger::value::DecodedRecord;
+
+use std::collections::{ HashSet, HashMap };
+use std::error::Error;
+use std::fmt;
+use std::fs::{ self, File };
+use std::io::{ self, Write, BufWriter, IsTerminal };
+use std::path::{ Path, PathBuf };
+use std::process::exit;
+use std::sync::{ Arc, Mutex, Condvar };
+use std::sync::atomic::{ AtomicUsize, AtomicU64, Ordering };
+use std::thread;
+use std::time::{ SystemTime, UNIX_EPOCH };
+use clap::{ Parser, Subcommand, CommandFactory, ArgAction };
+use log::LevelFilter;
+use clap_complete::{ generate, Shell };
 
-use rmerger::file::{ memory_map_read, PartRDB};
-use rmerger::parser::{ rdb, RDB, RDBSer, Database, DatabaseNumber };
+/// invalid command line, reported with exit status 2
+#[derive(Debug)]
+struct UsageError(String);
 
-use std::collections::HashSet;
-use nom::IResult;
-use getopts::Options;
+impl fmt::Display for UsageError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl Error for UsageError {}
+
+fn usage_error<E: fmt::Display>(e: E) -> Box<dyn Error> {
+    Box::new(UsageError(e.to_string()))
+}
+
+/// error message with a hint at the likely cause
+fn describe(e: &RmergerError) -> String {
+    match e {
+        &RmergerError::Input(ref name, ref e) => format!("{}: {}", name, describe(e)),
+        &RmergerError::ParseIncomplete => "unexpected end of file, the file is truncated".to_string(),
+        &RmergerError::ParseError(_) => format!("{}, the file is not an RDB file or is corrupted", e),
+        &RmergerError::ParseErrorAt { ref error, .. } => match **error {
+            RmergerError::ParseIncomplete => format!("{}, the file is truncated", e),
+            _                             => format!("{}, the file is not an RDB file or is corrupted", e),
+        },
+        &RmergerError::ChecksumMismatch { .. } => format!("{}, the file is corrupted", e),
+        &RmergerError::UnsupportedValueType(_) => format!("{}, only the value types of RDB v6 are supported", e),
+        &RmergerError::UnsupportedVersion(_) => format!("{}, RDB v{} to v{} are supported", e, MIN_RDB_VERSION, MAX_RDB_VERSION),
+        &RmergerError::DuplicateKey(_) => format!("{} (--conflict error)", e),
+        &RmergerError::Locked(_) => format!("{}, another rmerger is using the work directory (--no-lock to skip)", e),
+        e => e.to_string(),
+    }
+}
+
+fn print_error(e: &(dyn Error + 'static)) {
+    match e.downcast_ref::<RmergerError>() {
+        Some(e) => eprintln!("[error] {}", describe(e)),
+        None    => eprintln!("[error] {}", e),
+    }
+}
 
 fn main() {
-    let args: Vec<String> = std::env::args().collect();
-    let program = args[0].clone();
-    let mut opts = Options::new();
+    if let Err(e) = run() {
+        print_error(&*e);
+        if e.is::<UsageError>() {
+            eprintln!("Try '--help' for more information.");
+            exit(2);
+        }
+        exit(1);
+    }
+}
+
+/// command line options
+#[derive(Parser, Debug)]
+#[command(name = "rmerger", version, about = "Merge Redis RDB files", override_usage = "rmerger [Options] FILE.rdb ...\n       rmerger diff [--format FORMAT] A.rdb B.rdb")]
+struct Cli {
+    /// DB number(s) to export specially
+    #[arg(short = 'd', long = "database", value_name = "DATABASE")]
+    database: Vec<u32>,
+
+    /// merge only the keys matching GLOB (*, ? and [...] as in Redis SCAN MATCH)
+    #[arg(long = "match", value_name = "GLOB")]
+    key_match: Option<String>,
+
+    /// merge only the keys listed in FILE, one per line (# for comments)
+    #[arg(long = "include-keys-from", value_name = "FILE")]
+    include_keys_from: Option<String>,
+
+    /// leave out the keys listed in FILE, one per line, unless --include-keys-from lists them
+    #[arg(long = "exclude-keys-from", value_name = "FILE")]
+    exclude_keys_from: Option<String>,
+
+    /// write at most N keys
+    #[arg(long = "max-keys", value_name = "N")]
+    max_keys: Option<u64>,
+
+    /// keep only every K-th record, e.g. to make a small test fixture
+    #[arg(long = "sample-every", value_name = "K", value_parser = clap::value_parser!(u64).range(1..))]
+    sample_every: Option<u64>,
+
+    /// keep each record with probability F (0.0 to 1.0)
+    #[arg(long = "sample-fraction", value_name = "F", value_parser = parse_fraction)]
+    sample_fraction: Option<f64>,
+
+    /// seed of --sample-fraction, the same seed giving the same output [default: 0]
+    #[arg(long = "seed", value_name = "U64", requires = "sample_fraction")]
+    seed: Option<u64>,
+
+    /// merge only the values of TYPE (repeatable)
+    #[arg(long = "type", value_name = "TYPE", value_parser = ["string", "list", "set", "zset", "hash", "stream"])]
+    value_type: Vec<String>,
+
+    /// write database SRC into database DST
+    #[arg(long = "db-map", visible_alias = "remap", value_name = "SRC:DST", value_parser = parse_db_mapping)]
+    db_map: Vec<(u32, u32)>,
+
+    /// add N to every database number not given by --db-map
+    #[arg(long = "db-offset", value_name = "N", default_value_t = 0)]
+    db_offset: u32,
+
+    /// write every database into database DB (0 if omitted)
+    #[arg(long = "flatten", visible_alias = "single-db", value_name = "DB", num_args = 0..=1, require_equals = true, default_missing_value = "0")]
+    flatten: Option<u32>,
+
+    /// output/working directory
+    #[arg(short = 'o', long = "output", value_name = "DIRECTORY", default_value = "./")]
+    output: String,
+
+    /// directory of the part files and the stdin temporary file [default: the output directory]
+    #[arg(long = "work-dir", value_name = "PATH")]
+    work_dir: Option<String>,
+
+    /// merged file name in the output directory, or absolute path [default: MERGE.rdb]
+    #[arg(short = 'f', long = "output-file", value_name = "NAME")]
+    output_file: Option<String>,
+
+    /// write the merged RDB file to stdout instead of MERGE.rdb; messages go to stderr
+    #[arg(long = "stdout", conflicts_with_all = ["split_by_db", "output_file"])]
+    stdout: bool,
+
+    /// write the keys starting with PREFIX into OUTFILE.rdb (MERGE_<PREFIX>.rdb if omitted) (repeatable)
+    #[arg(long = "split-by-prefix", value_name = "PREFIX[:OUTFILE]", conflicts_with_all = ["stdout", "split_by_db"])]
+    split_by_prefix: Vec<String>,
+
+    /// separator which ends a --split-by-prefix PREFIX in a key
+    #[arg(long = "split-prefix-separator", value_name = "SEP", default_value = ":")]
+    split_prefix_separator: String,
+
+    /// write each output database into its own file MERGE_<DB>.rdb
+    #[arg(long = "split-by-db")]
+    split_by_db: bool,
+
+    /// prefix of the part files PREFIX<DB>.rdb, e.g. to run several merges in one directory
+    #[arg(long = "prefix", value_name = "PREFIX", default_value = "PART_")]
+    part_prefix: String,
+
+    /// suffix of the part files PART_<DB><SUFFIX>
+    #[arg(long = "part-suffix", value_name = "SUFFIX", default_value = ".rdb")]
+    part_suffix: String,
+
+    /// suffix of the temporary merged file MERGE.rdb.<pid><SUFFIX> [default: .tmp]
+    #[arg(long = "output-tmp-suffix", value_name = "SUFFIX")]
+    output_tmp_suffix: Option<String>,
+
+    /// write zeros instead of the CRC64 checksum of the merged file
+    #[arg(long = "no-checksum")]
+    no_checksum: bool,
+
+    /// do not check duplication of keys
+    #[arg(short = 'C', long = "nocheck", conflicts_with_all = ["conflict", "merge"])]
+    nocheck: bool,
+
+    /// check duplication of keys with a Bloom filter sized for N keys instead of holding every key;
+    /// a false positive drops a key which is not a duplicate
+    #[arg(long = "bloom", value_name = "N", conflicts_with = "nocheck")]
+    bloom: Option<u64>,
+
+    /// check duplication with one set of the output database and key of the records written,
+    /// shared by the --split-by-prefix outputs, keeping the first record of a key
+    #[arg(long = "global-dedup", conflicts_with_all = ["nocheck", "bloom"])]
+    global_dedup: bool,
+
+    /// false positive rate of --bloom [default: 0.01]
+    #[arg(long = "bloom-error-rate", value_name = "RATE", requires = "bloom", value_parser = parse_error_rate)]
+    bloom_error_rate: Option<f64>,
+
+    /// merge only the keys found in every input file
+    #[arg(long = "intersect")]
+    intersect: bool,
+
+    /// merge only the keys of the first input file which are in none of the others
+    #[arg(long = "difference", conflicts_with = "intersect")]
+    difference: bool,
+
+    /// remove the part files after a successful merge (default)
+    #[arg(long = "cleanup", overrides_with = "no_cleanup")]
+    cleanup: bool,
+
+    /// keep the part files after the merge
+    #[arg(long = "no-cleanup", visible_alias = "keep-parts", overrides_with = "cleanup")]
+    no_cleanup: bool,
+
+    /// remove the --work-dir directory after the merge if it is then empty
+    #[arg(long = "delete-parts", requires = "work_dir", conflicts_with = "no_cleanup")]
+    delete_parts: bool,
+
+    /// do not lock the work directory, the output directory by default, against other rmerger processes
+    #[arg(long = "no-lock")]
+    no_lock: bool,
+
+    /// gzip the part files and the merged file MERGE.rdb.gz
+    #[arg(long = "gzip")]
+    gzip: bool,
+
+    /// keep the records in memory instead of part files; MERGE.rdb goes into ./ or -f, the lock and temporary files into --work-dir or ./
+    #[arg(long = "in-memory", conflicts_with = "output")]
+    in_memory: bool,
+
+    /// write ziplist and intset values as plain lists, sets, sorted sets and hashes
+    #[arg(long = "normalize-encodings")]
+    normalize_encodings: bool,
+
+    /// write small lists, sorted sets and hashes as ziplists, which are smaller
+    #[arg(long = "encode-ziplists", conflicts_with = "normalize_encodings")]
+    encode_ziplists: bool,
+
+    /// most entries of a list, or members or fields of a sorted set or hash, written as a ziplist
+    #[arg(long = "ziplist-max-entries", value_name = "N", default_value_t = ZiplistLimits::default().max_entries, requires = "encode_ziplists")]
+    ziplist_max_entries: usize,
+
+    /// most bytes of an element, member, field or value of a ziplist
+    #[arg(long = "ziplist-max-value", value_name = "BYTES", default_value_t = ZiplistLimits::default().max_value, requires = "encode_ziplists")]
+    ziplist_max_value: usize,
+
+    /// record kept for a duplicate key: first-wins, last-wins, longest-ttl, shortest-ttl, largest-value, smallest-value or error [default: first-wins]
+    #[arg(long = "conflict", value_name = "STRATEGY")]
+    conflict: Option<ConflictStrategy>,
+
+    /// part of the key compared to find duplicates: full, prefix:N or suffix:N (the first or
+    /// last N bytes) or hash (a 64-bit hash, for long keys)
+    #[arg(long = "dedup-by", value_name = "STRATEGY", default_value = "full", conflicts_with = "nocheck")]
+    dedup_by: DedupKey,
+
+    /// values kept for a duplicate list, set, sorted set or hash key: replace or union
+    #[arg(long = "merge", value_name = "STRATEGY", default_value = "replace")]
+    merge: MergeStrategy,
+
+    /// leave out keys which have already expired
+    #[arg(long = "drop-expired")]
+    drop_expired: bool,
+
+    /// aux field copied into the merged file [default: redis-ver and redis-bits]
+    #[arg(long = "aux", value_name = "KEY")]
+    aux: Vec<String>,
+
+    /// RDB version of the merged file, which must have the value types written [default: the highest
+    /// version of the input files]
+    #[arg(long = "force-version", visible_alias = "rdb-version", value_name = "VER",
+          value_parser = clap::value_parser!(u32).range(MIN_RDB_VERSION as i64..=MAX_RDB_VERSION as i64))]
+    force_version: Option<u32>,
+
+    /// output format
+    #[arg(long = "format", value_name = "FORMAT", default_value = "rdb", value_parser = ["rdb", "json", "restore", "resp", "commands"])]
+    format: String,
+
+    /// write the records of the merged file into the Redis server at URL (redis://[[USER]:PASSWORD@]HOST[:PORT][/DB]), into database DB if given
+    #[arg(long = "replay", value_name = "URL", conflicts_with_all = ["stdout", "dry_run"])]
+    replay: Option<ReplayTarget>,
+
+    /// skip the records of a value type which is not supported, such as streams, with a warning instead of failing
+    #[arg(long = "skip-unsupported")]
+    skip_unsupported: bool,
+
+    /// print the number of keys and value bytes of each type for each input database
+    #[arg(long = "histogram")]
+    histogram: bool,
 
-    opts.optmulti("d", "database", "DB number(s) to export specially", "DATABASE" );
-    opts.optopt  ("o", "output",   "output/working directory",         "DIRECTORY");
-    opts.optflag ("C", "nocheck",  "do not check duplication of keys");
-    opts.optflag ("h", "help",     "display this help and exit");
+    /// print the number of records by value size and size percentiles, for each input database and in total
+    #[arg(long = "size-histogram")]
+    size_histogram: bool,
 
-    let matches = opts.parse(&args[1..]).unwrap();
-    if matches.opt_present("h") {
-        print_usage(&program, opts);
-        return;
+    /// print merge statistics
+    #[arg(long = "stats")]
+    stats: bool,
+
+    /// write merge statistics as JSON
+    #[arg(long = "stats-file", value_name = "PATH")]
+    stats_file: Option<String>,
+
+    /// scan the input files first and stop before writing anything if the merged file is
+    /// estimated to be larger than N bytes
+    #[arg(long = "max-output-bytes", value_name = "N")]
+    max_output_bytes: Option<usize>,
+
+    /// multiply the estimate of --max-output-bytes by F, e.g. 1.2 for a safety margin
+    #[arg(long = "output-overhead", value_name = "F", default_value_t = DEFAULT_OUTPUT_OVERHEAD, requires = "max_output_bytes")]
+    output_overhead: f64,
+
+    /// report progress every N records (1000000 if omitted) and while merging
+    #[arg(long = "progress", value_name = "N", num_args = 0..=1, default_missing_value = "1000000")]
+    progress: Option<u64>,
+
+    /// do not show a counter of the records written when stdout is a terminal
+    #[arg(long = "no-progress")]
+    no_progress: bool,
+
+    /// number of input files parsed in parallel
+    #[arg(short = 'j', long = "jobs", value_name = "N", default_value_t = 1, value_parser = clap::value_parser!(u32).range(1..))]
+    jobs: u32,
+
+    /// read the input files and print statistics without writing any file
+    #[arg(short = 'n', long = "dry-run")]
+    dry_run: bool,
+
+    /// check the input files (parse, checksum, keys) and print a report of each instead of merging;
+    /// the exit status is 0 only if every file passes
+    #[arg(long = "validate", visible_alias = "check")]
+    validate: bool,
+
+    /// print the records of the input files as JSON lines instead of merging
+    #[cfg(feature = "serde")]
+    #[arg(long = "json", conflicts_with = "validate")]
+    json: bool,
+
+    /// write one CSV row of metadata per key of the input files into FILE, or stdout for -, instead of merging:
+    /// db, key, value type, number of elements, serialized size and expiry
+    #[arg(long = "csv", value_name = "FILE", conflicts_with = "validate")]
+    csv: Option<String>,
+
+    /// print debug messages, and trace messages if repeated
+    #[arg(short = 'v', long = "verbose", action = ArgAction::Count)]
+    verbose: u8,
+
+    /// print warnings and errors only
+    #[arg(short = 'q', long = "quiet", conflicts_with = "verbose")]
+    quiet: bool,
+
+    /// log format: text or json, one JSON object per line
+    #[arg(long = "log-format", value_name = "FORMAT", default_value = "text", value_parser = ["text", "json"])]
+    log_format: String,
+
+    /// print a completion script for SHELL and exit
+    #[arg(long = "generate-completion", value_name = "SHELL")]
+    generate_completion: Option<Shell>,
+
+    /// read an RDB file from stdin after the input files, like FILE.rdb `-`
+    #[arg(long = "stdin")]
+    stdin: bool,
+
+    /// copy stdin into a temporary file in the output directory instead of memory
+    #[arg(long = "stdin-temp-file")]
+    stdin_temp_file: bool,
+
+    /// read more input files from FILE, one path per line (# for comments), after FILE.rdb ...
+    #[arg(long = "manifest", value_name = "FILE")]
+    manifest: Option<String>,
+
+    /// merge an input file given more than once (by a path or the manifest) that many times
+    #[arg(long = "allow-duplicate-inputs")]
+    allow_duplicate_inputs: bool,
+
+    /// input RDB files, `-` or none for stdin, or http:// and https:// URLs with the http feature
+    #[arg(value_name = "FILE.rdb")]
+    files: Vec<String>,
+
+    #[command(subcommand)]
+    command: Option<Command>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// report the keys only in A, only in B, and in both with the same or a different value;
+    /// the exit status is 1 if the files differ and 2 on an error
+    Diff {
+        /// report format: table or json
+        #[arg(long = "format", value_name = "FORMAT", default_value = "table", value_parser = ["table", "json"])]
+        format: String,
+
+        #[arg(value_name = "A.rdb")]
+        a: String,
+
+        #[arg(value_name = "B.rdb")]
+        b: String,
+    },
+}
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let cli = Cli::parse();
+    if let Some(shell) = cli.generate_completion {
+        let mut script = Vec::new();
+        generate(shell, &mut Cli::command(), "rmerger", &mut script);
+        io::stdout().write_all(&script)?;
+        return Ok(());
+    }
+
+    let level = match (cli.quiet, cli.verbose) {
+        (true, _) => LevelFilter::Warn,
+        (_, 0)    => LevelFilter::Info,
+        (_, 1)    => LevelFilter::Debug,
+        _         => LevelFilter::Trace,
+    };
+    let format = if cli.log_format == "json" { LogFormat::Json } else { LogFormat::Text };
+    // messages go to stderr when stdout carries the merged file, the CSV or the JSON lines
+    #[allow(unused_mut)]
+    let mut stderr_only = cli.stdout || cli.csv.as_deref() == Some(STDOUT_NAME);
+    #[cfg(feature = "serde")]
+    { stderr_only |= cli.json; }
+    #[cfg(not(feature = "tracing"))]
+    StdLogger::new(level).stderr_only(stderr_only).format(format).install()?;
+    #[cfg(feature = "tracing")]
+    init_tracing(level, format, stderr_only).map_err(|e| e as Box<dyn Error>)?;
+
+    // as diff(1): 0 if the files are the same, 1 if they differ and 2 if they cannot be compared
+    if let Some(Command::Diff { ref format, ref a, ref b }) = cli.command {
+        match diff(a, b, format) {
+            Ok(true)  => return Ok(()),
+            Ok(false) => exit(1),
+            Err(e)    => {
+                print_error(&*e);
+                exit(2);
+            },
+        }
     }
 
-    let target_db = database_set(matches.opt_strs("d")).unwrap();
+    // input files downloaded from URLs, removed on return
+    let mut downloads = Vec::new();
+    if cli.validate {
+        return validate(&input_files(&cli, &mut downloads)?);
+    }
+
+    let target_db: HashSet<u32> = cli.database.iter().cloned().collect();
     if target_db.is_empty() {
-        println!("[info] target DB: ALL");
+        info!("target DB: ALL");
     } else {
-        println!("[info] target DB: {:?}", target_db);
+        info!("target DB: {:?}", target_db);
     }
 
-    let check_duplication = !matches.opt_present("C");
-    println!("[info] check duplication of keys: {}", check_duplication);
+    #[cfg(feature = "serde")]
+    {
+        if cli.json {
+            return dump_json(&input_files(&cli, &mut downloads)?, &target_db);
+        }
+    }
 
-    let output_dir = matches.opt_str("o").unwrap_or("./".to_string());
-    println!("[info] output directory: {}", output_dir);
+    if let Some(ref path) = cli.csv {
+        return dump_csv(&input_files(&cli, &mut downloads)?, &target_db, path);
+    }
 
-    let mut srdb = PartRDB::new(check_duplication, output_dir).unwrap();
+    if let Some(ref glob) = cli.key_match {
+        info!("key pattern: {}", glob);
+    }
 
-    for arg in matches.free {
-        println!("[info] start: {}", arg);
-        let file = std::fs::File::open(arg.clone()).unwrap();
+    let mut type_filter = HashSet::new();
+    for name in &cli.value_type {
+        type_filter.extend(value_types(name).map_err(usage_error)?);
+    }
+    if !cli.value_type.is_empty() {
+        info!("value types: {:?}", cli.value_type);
+    }
 
-        memory_map_read(&file, |s| {
-            match rdb(s) {
-                IResult::Done(_, RDB(ver, dbs, _)) => {
-                    println!("[info] version: {}", ver.to_string().unwrap());
-                    for db in dbs {
-                        let Database(db_num, records) = db;
-                        let DatabaseNumber(_, num) = db_num;
-                        if target_db.is_empty() || target_db.contains(&num) {
-                            for record in records {
-                                srdb.write(db_num, &record, true).unwrap();
-                            }
-                        }
-                    }
-                },
-                result => panic!("parse error: {:?}", result),
+    let db_map = database_map(&cli.db_map).map_err(usage_error)?;
+    let db_offset = cli.db_offset;
+    if !db_map.is_empty() || db_offset != 0 {
+        info!("database map: {:?}, offset: {}", db_map, db_offset);
+    }
+
+    let flatten = cli.flatten;
+    if let Some(db) = flatten {
+        info!("flatten into DB: {}", db);
+    }
+
+    let check_duplication = !cli.nocheck;
+    info!("check duplication of keys: {}", check_duplication);
+
+    let conflict = cli.conflict.unwrap_or(ConflictStrategy::FirstWins);
+    info!("conflict strategy: {:?}", conflict);
+
+    let merge = cli.merge;
+    info!("merge strategy: {:?}", merge);
+
+    let output_dir = cli.output.clone();
+    info!("output directory: {}", output_dir);
+
+    let format = cli.format.clone();
+    info!("output format: {}", format);
+    if cli.stdout && format != "rdb" {
+        return Err(usage_error("--stdout only supports the rdb format"));
+    }
+
+    let mut srdb = PartRDB::new_unlocked(check_duplication, conflict, output_dir)?;
+    if cli.in_memory {
+        info!("keep part files in memory");
+        srdb.set_in_memory(true);
+    }
+    if cli.normalize_encodings {
+        info!("write ziplist and intset values as plain types");
+        srdb.set_normalize_encodings(true);
+    }
+    if cli.encode_ziplists {
+        info!("write lists, sorted sets and hashes of up to {} entries of {} bytes as ziplists", cli.ziplist_max_entries, cli.ziplist_max_value);
+        srdb.set_encode_ziplists(ZiplistLimits { max_entries: cli.ziplist_max_entries, max_value: cli.ziplist_max_value });
+    }
+    srdb.set_db_map(db_map, db_offset).map_err(usage_error)?;
+    srdb.set_merge_strategy(merge);
+    if cli.dedup_by != DedupKey::Full {
+        info!("duplicate keys by: {:?}", cli.dedup_by);
+        srdb.set_dedup_key(cli.dedup_by);
+    }
+    if let Some(n) = cli.bloom {
+        let rate = cli.bloom_error_rate.unwrap_or(DEFAULT_BLOOM_ERROR_RATE);
+        srdb.set_bloom(n, rate).map_err(|_| usage_error("--bloom only supports --conflict first-wins and --merge replace"))?;
+        info!("check duplication with a Bloom filter for {} keys (false positive rate {})", n, rate);
+    }
+    if cli.global_dedup {
+        srdb.set_global_dedup().map_err(|_| usage_error("--global-dedup only supports --conflict first-wins and --merge replace"))?;
+        info!("check duplication with one set of keys");
+    }
+    if let Some(ref glob) = cli.key_match {
+        srdb.set_key_filter(Pattern::new(glob));
+    }
+    if let Some(ref path) = cli.include_keys_from {
+        let keys = read_key_list(path).map_err(|e| e.in_file(path))?;
+        info!("keys to include: {} from {}", keys.len(), path);
+        srdb.set_include_keys(keys);
+    }
+    if let Some(ref path) = cli.exclude_keys_from {
+        let keys = read_key_list(path).map_err(|e| e.in_file(path))?;
+        info!("keys to exclude: {} from {}", keys.len(), path);
+        srdb.set_exclude_keys(keys);
+    }
+    srdb.set_value_type_filter(type_filter);
+    if let Some(n) = cli.max_keys {
+        info!("maximum number of keys: {}", n);
+        srdb.set_max_keys(n);
+    }
+    if let Some(k) = cli.sample_every {
+        info!("keep every {}th record", k);
+        srdb.set_sample_every(k);
+    }
+    if let Some(fraction) = cli.sample_fraction {
+        let seed = cli.seed.unwrap_or(DEFAULT_SEED);
+        info!("keep records with probability {} (seed {})", fraction, seed);
+        srdb.set_sample_fraction(fraction, seed);
+    }
+    srdb.set_checksum(!cli.no_checksum);
+    if cli.gzip {
+        if format != "rdb" {
+            return Err(usage_error("--gzip only supports the rdb format"));
+        }
+        info!("gzip output");
+        srdb.set_gzip(true);
+    }
+    if let Some(ref dir) = cli.work_dir {
+        srdb.set_work_dir(dir).map_err(usage_error)?;
+        info!("work directory: {}", dir);
+    }
+    // the part files are guarded by the lock, which a dry run does not write
+    let lock = if cli.no_lock || cli.dry_run {
+        info!("do not lock the work directory");
+        None
+    } else {
+        Some(acquire_lock(Path::new(cli.work_dir.as_ref().unwrap_or(&cli.output)))?)
+    };
+    srdb.set_cleanup(!cli.no_cleanup);
+    srdb.set_part_file_name(&cli.part_prefix, &cli.part_suffix).map_err(usage_error)?;
+    if let Some(ref name) = cli.output_file {
+        srdb.set_merge_file(name).map_err(usage_error)?;
+        info!("output file: {:?}", srdb.merge_path());
+    }
+    if cli.split_by_db {
+        if format != "rdb" {
+            return Err(usage_error("--split-by-db only supports the rdb format"));
+        }
+        info!("split output by database");
+        srdb.set_split_by_db(true);
+    }
+    if let Some(ref suffix) = cli.output_tmp_suffix {
+        srdb.set_tmp_suffix(suffix.clone());
+    }
+    if !cli.aux.is_empty() {
+        srdb.set_aux_keys(cli.aux.clone());
+    }
+    if let Some(version) = cli.force_version {
+        info!("RDB version of the merged file: {}", version);
+        srdb.set_force_version(version);
+    }
+    if cli.drop_expired {
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+        info!("drop keys expired before: {} ms", now_ms);
+        srdb.set_drop_expired(now_ms);
+    }
+    let dry_run = cli.dry_run;
+    srdb.set_dry_run(dry_run);
+    if dry_run {
+        info!("dry run: no file is written");
+    }
+    if let Some(db) = flatten {
+        srdb.set_flatten(db, cli.conflict.is_none());
+    }
+
+    let jobs = cli.jobs as usize;
+    if jobs > 1 {
+        info!("jobs: {}", jobs);
+    }
+
+    // size of the input file being written, for the progress percentage
+    let input_size = Arc::new(AtomicU64::new(0));
+    if let Some(every) = cli.progress {
+        let input_size = input_size.clone();
+        srdb.set_progress(every, move |progress| match progress {
+            Progress::Write { records, bytes } => {
+                let size = input_size.load(Ordering::SeqCst).max(1);
+                info!("progress: {} records, {}%", records, (bytes * 100 / size).min(100));
+            },
+            Progress::Merge { bytes, total } => {
+                info!("progress: merge {} / {} bytes", bytes, total);
+            },
+        });
+    }
+
+    // a counter line on the terminal, unless the progress is logged or only warnings are wanted
+    let tty_progress = !cli.no_progress && cli.progress.is_none() && !cli.quiet && io::stdout().is_terminal();
+    if tty_progress {
+        srdb.set_progress_callback(Box::new(TtyProgress::default()));
+    }
+
+    let mut srdb = SplitRDB::new(srdb);
+    if !cli.split_by_prefix.is_empty() {
+        if format != "rdb" {
+            return Err(usage_error("--split-by-prefix only supports the rdb format"));
+        }
+        srdb.set_separator(&cli.split_prefix_separator);
+        for spec in &cli.split_by_prefix {
+            let (prefix, name) = prefix_route(spec);
+            srdb.add_route(&prefix, &name).map_err(usage_error)?;
+            info!("split keys of {} into: {}", prefix, name);
+        }
+    }
+
+    let mut files = input_files(&cli, &mut downloads)?;
+    if files.iter().filter(|f| *f == STDIN_NAME).count() > 1 {
+        return Err(usage_error("stdin can be read only once"));
+    }
+    let work_dir = Path::new(cli.work_dir.as_ref().unwrap_or(&cli.output));
+    let spool_dir = if cli.stdin_temp_file { Some(work_dir) } else { None };
+
+    if let Some(max) = cli.max_output_bytes {
+        let size = estimate_size(&files, cli.output_overhead)?;
+        info!("estimated output size: {} bytes, {} bytes on disk while merging", size, estimate_disk_usage(size, cli.in_memory));
+        if size > max {
+            return Err(format!("estimated output size of {} bytes exceeds --max-output-bytes {}", size, max).into());
+        }
+    }
+
+    if cli.intersect {
+        if files.iter().any(|f| f == STDIN_NAME) {
+            return Err(usage_error("--intersect cannot read stdin twice"));
+        }
+        let mut common: Option<HashSet<(u32, Vec<u8>)>> = None;
+        for path in &files {
+            info!("collect keys: {}", path);
+            let keys = collect_keys(path, &target_db, cli.skip_unsupported, &srdb)?;
+            common = Some(match common {
+                Some(common) => common.into_iter().filter(|k| keys.contains(k)).collect(),
+                None         => keys,
+            });
+        }
+        let common = common.unwrap_or_default();
+        info!("keys in every input file: {}", common.len());
+        srdb.set_key_intersection(common);
+    }
+
+    let mut srdb = DiffRDB::new(srdb);
+    if cli.difference {
+        if files.iter().skip(1).any(|f| f == STDIN_NAME) {
+            return Err(usage_error("--difference reads stdin only as the first input file"));
+        }
+        for path in files.iter().skip(1) {
+            info!("collect keys: {}", path);
+            exclude_keys(path, &target_db, cli.skip_unsupported, &mut srdb)?;
+        }
+        info!("keys in the other input files: {}", srdb.excluded());
+        files.truncate(1);
+    }
+
+    let srdb = Mutex::new(srdb);
+    let read_opts = ReadOptions { target_db: &target_db, spool_dir, skip_unsupported: cli.skip_unsupported };
+    let turns = Turns::new();
+    let next_file = AtomicUsize::new(0);
+    let files = &files;
+    let errors: Vec<Box<dyn Error>> = thread::scope(|scope| {
+        let workers: Vec<_> = (0..jobs.min(files.len())).map(|_| scope.spawn(|| -> error::Result<()> {
+            loop {
+                let i = next_file.fetch_add(1, Ordering::SeqCst);
+                if i >= files.len() {
+                    return Ok(());
+                }
+                if let Err(e) = read_input(&files[i], i, &read_opts, &srdb, &turns, &input_size) {
+                    turns.done(false);
+                    return Err(e);
+                }
+            }
+        })).collect();
+        workers.into_iter().filter_map(|w| match w.join() {
+            Ok(result) => result.err().map(|e| Box::new(e) as Box<dyn Error>),
+            Err(_)     => Some("worker thread panicked".into()),
+        }).collect()
+    });
+    if let Some(e) = errors.into_iter().next() {
+        return Err(e);
+    }
+    let mut srdb = srdb.into_inner().map_err(|_| "worker thread panicked")?;
+    if tty_progress {
+        // clear the counter line
+        print!("\r\x1b[K");
+        io::stdout().flush()?;
+    }
+
+    info!("start: merge");
+    srdb.close_part_files()?;
+    if cli.stdout {
+        srdb.merge_to(BufWriter::new(io::stdout()))?;
+        info!("finish: merge to stdout");
+    } else {
+        srdb.merge()?;
+        info!("finish: merge {:?}", srdb.merge_paths());
+    }
+    // the lock file is removed with the lock, leaving the work directory empty
+    drop(lock);
+    if cli.delete_parts {
+        match std::fs::remove_dir(work_dir) {
+            Ok(()) => info!("remove work directory: {:?}", work_dir),
+            Err(e) => warn!("failed to remove work directory {:?}: {}", work_dir, e),
+        }
+    }
+    let srdb = srdb.into_inner();
+
+    // tables go to stderr while stdout carries the merged file
+    let mut report: Box<dyn Write> = if cli.stdout { Box::new(io::stderr()) } else { Box::new(io::stdout()) };
+
+    if let Some(ref target) = cli.replay {
+        for path in srdb.merge_paths() {
+            info!("start: replay {:?} into {}:{}", path, target.host, target.port);
+            replay(&path, target)?;
+            info!("finish: replay {:?}", path);
+        }
+    }
+
+    if format != "rdb" && !dry_run {
+        let rdb_path = srdb.merge_path();
+        info!("start: export {}", format);
+        let path = export(&rdb_path, &format)?;
+        std::fs::remove_file(&rdb_path)?;
+        info!("finish: export {:?}", path);
+    }
+
+    if cli.histogram {
+        for db in srdb.histogram_dbs() {
+            if let Some(histogram) = srdb.type_histogram(db) {
+                writeln!(report, "database {}", db)?;
+                write!(report, "{}", histogram)?;
+            }
+        }
+    }
+
+    if cli.size_histogram {
+        for db in srdb.histogram_dbs() {
+            if let Some(histogram) = srdb.size_histogram(db) {
+                writeln!(report, "database {}", db)?;
+                write!(report, "{}", histogram)?;
+            }
+        }
+        writeln!(report, "all databases")?;
+        write!(report, "{}", srdb.global_size_histogram())?;
+    }
+
+    let stats = srdb.stats();
+    if cli.include_keys_from.is_some() || cli.exclude_keys_from.is_some() {
+        info!("records filtered out by key lists: {}", stats.filtered);
+    }
+    if cli.stats || dry_run {
+        write!(report, "{}", stats)?;
+    }
+    if let Some(ref path) = cli.stats_file {
+        File::create(path)?.write_all(stats.to_json().as_bytes())?;
+        info!("statistics: {}", path);
+    }
+    Ok(())
+}
+
+// records between two updates of the counter line of TtyProgress
+const TTY_PROGRESS_EVERY: u64 = 10000;
+
+/// counter of the records written, overwriting its line on the terminal
+#[derive(Default)]
+struct TtyProgress {
+    records: AtomicU64,
+}
+
+impl ProgressCallback for TtyProgress {
+    fn on_record(&self, db: u32, _key: &str, bytes_written: usize) {
+        let records = self.records.fetch_add(1, Ordering::SeqCst) + 1;
+        if records.is_multiple_of(TTY_PROGRESS_EVERY) {
+            print!("\r{} records, {} bytes, database {}\x1b[K", records, bytes_written, db);
+            let _ = io::stdout().flush();
+        }
+    }
+}
+
+
+/// lets the `--jobs` workers parse input files in parallel but write them in command line order
+struct Turns {
+    // index of the file to write next, and whether a file has failed
+    state: Mutex<(usize, bool)>,
+    cond:  Condvar,
+}
+
+impl Turns {
+    fn new() -> Self {
+        Turns { state: Mutex::new((0, false)), cond: Condvar::new() }
+    }
+
+    /// wait until file `index` may be written; false if another file has failed
+    fn wait(&self, index: usize) -> bool {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        while state.0 != index && !state.1 {
+            state = self.cond.wait(state).unwrap_or_else(|e| e.into_inner());
+        }
+        !state.1
+    }
+
+    /// whether a file has failed, so that the other files need not be parsed
+    fn failed(&self) -> bool {
+        self.state.lock().unwrap_or_else(|e| e.into_inner()).1
+    }
+
+    /// pass the turn to the next file, or stop every worker unless `ok`
+    fn done(&self, ok: bool) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if ok {
+            state.0 += 1;
+        } else {
+            state.1 = true;
+        }
+        self.cond.notify_all();
+    }
+}
+
+
+/// how the worker threads read the input files
+struct ReadOptions<'a> {
+    target_db:        &'a HashSet<u32>,
+    spool_dir:        Option<&'a Path>,
+    skip_unsupported: bool,
+}
+
+/// parse the input file `s`, skipping the records of unsupported value types if `skip_unsupported`,
+/// and verify its checksum
+fn parse_input(s: &[u8], skip_unsupported: bool) -> error::Result<RDB<'_>> {
+    let r = if skip_unsupported { parse_rdb_skipping(s)? } else { parse_rdb(s)? };
+    verify_checksum(s, r.3)?;
+    Ok(r)
+}
+
+/// parse input file `path`, the `index`-th one, and write its records when its turn comes
+fn read_input(path: &str, index: usize, opts: &ReadOptions, srdb: &Mutex<DiffRDB>, turns: &Turns, input_size: &AtomicU64)
+    -> error::Result<()>
+{
+    if turns.failed() {
+        return Ok(());
+    }
+    info!("start: {}", path);
+    let parse = |s: &mut [u8]| -> error::Result<()> {
+        if turns.failed() {
+            return Ok(());
+        }
+        let r = parse_input(s, opts.skip_unsupported)?;
+        if !turns.wait(index) {
+            return Ok(());
+        }
+        let mut srdb = srdb.lock().unwrap_or_else(|e| e.into_inner());
+        input_size.store(s.len() as u64, Ordering::SeqCst);
+        srdb.start_file(path);
+        info!("version: {}", r.0);
+        srdb.write_rdb(r, opts.target_db, true)?;
+        turns.done(true);
+        Ok(())
+    };
+    let result = if path == STDIN_NAME {
+        read_stdin(opts.spool_dir, parse)
+    } else {
+        let file = File::open(path).map_err(|e| RmergerError::from(e).in_file(path))?;
+        read_input_file(&file, parse)
+    };
+    result.and_then(|r| r).map_err(|e| e.in_file(path))?;
+
+    info!("finish: {}", path);
+    Ok(())
+}
+
+
+/// FILE.rdb ..., the files of --manifest and stdin for --stdin, checking that every file can be
+/// read and, unless --allow-duplicate-inputs, that none is given twice
+fn input_files(cli: &Cli, downloads: &mut Vec<TempOutput>) -> Result<Vec<String>, Box<dyn Error>> {
+    let mut files = cli.files.clone();
+    if let Some(ref manifest) = cli.manifest {
+        let paths = read_list_lines(manifest).map_err(|e| e.in_file(manifest))?;
+        info!("input files from {}: {}", manifest, paths.len());
+        files.extend(paths);
+    }
+    if cli.stdin || files.is_empty() {
+        files.push(STDIN_NAME.to_string());
+    }
+
+    let mut seen: HashMap<PathBuf, &str> = HashMap::new();
+    for path in files.iter().filter(|f| *f != STDIN_NAME) {
+        let canonical = if is_url(path) {
+            PathBuf::from(path)
+        } else {
+            File::open(path).map_err(|e| RmergerError::from(e).in_file(path))?;
+            fs::canonicalize(path).map_err(|e| RmergerError::from(e).in_file(path))?
+        };
+        if let Some(first) = seen.insert(canonical, path) {
+            if !cli.allow_duplicate_inputs {
+                return Err(usage_error(format!("{} is the same input file as {}", path, first)));
             }
-        }).unwrap();
+            warn!("{} is the same input file as {}, merged again", path, first);
+        }
+    }
 
-        println!("[info] finish: {}", arg);
+    // URLs are downloaded once, next to the part files, and then read as local files
+    let work_dir = Path::new(cli.work_dir.as_ref().unwrap_or(&cli.output));
+    let mut downloaded: HashMap<String, String> = HashMap::new();
+    for file in files.iter_mut().filter(|f| is_url(f)) {
+        let path = match downloaded.get(file.as_str()) {
+            Some(path) => path.clone(),
+            None       => download_input(file, work_dir, downloads)?,
+        };
+        downloaded.insert(file.clone(), path.clone());
+        *file = path;
     }
+    Ok(files)
+}
+
+/// download `url` into a temporary file in `dir`, kept in `downloads`, returning its path
+#[cfg(feature = "http")]
+fn download_input(url: &str, dir: &Path, downloads: &mut Vec<TempOutput>) -> Result<String, Box<dyn Error>> {
+    let name = format!("HTTP_{}.rdb", downloads.len());
+    info!("download: {}", redact_url(url));
+    let out = download(url, dir, &name).map_err(|e| e.in_file(&redact_url(url)))?;
+    let path = out.tmp_path().to_string_lossy().to_string();
+    downloads.push(out);
+    Ok(path)
+}
 
-    println!("[info] start: merge");
-    srdb.close_part_files();
-    srdb.merge().unwrap();
-    println!("[info] finish: merge");
+#[cfg(not(feature = "http"))]
+fn download_input(url: &str, _dir: &Path, _downloads: &mut Vec<TempOutput>) -> Result<String, Box<dyn Error>> {
+    Err(usage_error(format!("{}: URL input needs rmerger built with the http feature", redact_url(url))))
 }
 
+/// estimated size of the file merged from `files` times `overhead`, without stdin which cannot be read twice
+fn estimate_size(files: &[String], overhead: f64) -> Result<usize, Box<dyn Error>> {
+    let mut size = 0;
+    for path in files {
+        if path == STDIN_NAME {
+            warn!("stdin is left out of the estimated output size");
+            continue;
+        }
+        let file = File::open(path).map_err(|e| RmergerError::from(e).in_file(path))?;
+        size += read_input_file(&file, |s| estimate_output_size_with(&[s], overhead))?.map_err(|e| e.in_file(path))?;
+    }
+    Ok(size)
+}
+
+/// print a report of each of `files`, failing if any of them does not pass, for --validate
+fn validate(files: &[String]) -> Result<(), Box<dyn Error>> {
+    let mut failed = 0;
+    for path in files {
+        info!("validate: {}", path);
+        let report = validate_file(path).map_err(|e| e.in_file(path))?;
+        if !report.is_valid() {
+            failed += 1;
+        }
+        print!("{}", report);
+    }
+    if failed > 0 {
+        return Err(format!("{} of {} files failed validation", failed, files.len()).into());
+    }
+    Ok(())
+}
+
+
+/// print the records of the databases in `target_db` (all if empty) of `files` as JSON lines, for --json
+#[cfg(feature = "serde")]
+fn dump_json(files: &[String], target_db: &HashSet<u32>) -> Result<(), Box<dyn Error>> {
+    let mut out = BufWriter::new(io::stdout());
+    for path in files {
+        info!("dump: {}", path);
+        let dump = |s: &mut [u8]| -> error::Result<()> {
+            let r = parse_rdb(s)?;
+            verify_checksum(s, r.3)?;
+            for (num, record) in (&r).into_iter().filter(|&(num, _)| target_db.is_empty() || target_db.contains(&num.1)) {
+                writeln!(out, "{}", DecodedRecord::decode(num.1, record)?.to_json()?)?;
+            }
+            Ok(())
+        };
+        let result = if path == STDIN_NAME {
+            read_stdin(None, dump)
+        } else {
+            let file = File::open(path).map_err(|e| RmergerError::from(e).in_file(path))?;
+            read_input_file(&file, dump)
+        };
+        result.and_then(|r| r).map_err(|e| e.in_file(path))?;
+    }
+    out.flush()?;
+    Ok(())
+}
+
+/// write a CSV row of metadata per record of `files` in `target_db` into `path`, or stdout for -
+fn dump_csv(files: &[String], target_db: &HashSet<u32>, path: &str) -> Result<(), Box<dyn Error>> {
+    let out: Box<dyn Write> = if path == STDOUT_NAME {
+        Box::new(io::stdout())
+    } else {
+        Box::new(File::create(path).map_err(|e| RmergerError::from(e).in_file(path))?)
+    };
+    let mut writer = RecordCsvWriter::new(out)?;
+    for path in files {
+        info!("csv: {}", path);
+        let dump = |s: &mut [u8]| -> error::Result<()> {
+            let r = parse_rdb(s)?;
+            verify_checksum(s, r.3)?;
+            for (num, record) in (&r).into_iter().filter(|&(num, _)| target_db.is_empty() || target_db.contains(&num.1)) {
+                writer.write_record(num.1, record)?;
+            }
+            Ok(())
+        };
+        let result = if path == STDIN_NAME {
+            read_stdin(None, dump)
+        } else {
+            let file = File::open(path).map_err(|e| RmergerError::from(e).in_file(path))?;
+            read_input_file(&file, dump)
+        };
+        result.and_then(|r| r).map_err(|e| e.in_file(path))?;
+    }
+    writer.finish()?;
+    Ok(())
+}
 
-fn print_usage(program: &str, opts: Options) {
-    let brief = format!("Usage: {} [Options] FILE.rdb ...", program);
-    print!("{}", opts.usage(&brief));
+
+/// print the differences between the RDB files `a` and `b` as `format`; true if there are none
+fn diff(a: &str, b: &str, format: &str) -> Result<bool, Box<dyn Error>> {
+    let fa = File::open(a).map_err(|e| RmergerError::from(e).in_file(a))?;
+    let fb = File::open(b).map_err(|e| RmergerError::from(e).in_file(b))?;
+    let report = read_input_file(&fa, |sa| {
+        read_input_file(&fb, |sb| diff_rdbs(sa, sb))
+    }).and_then(|r| r).and_then(|r| r)?;
+
+    let mut out = io::stdout();
+    match format {
+        "json" => out.write_all(report.to_json().as_bytes())?,
+        _      => write!(out, "{}", report)?,
+    }
+    out.flush()?;
+    Ok(report.is_equivalent())
+}
+
+
+/// output database and key of the records of input file `path`, for --intersect
+fn collect_keys(path: &str, target_db: &HashSet<u32>, skip_unsupported: bool, srdb: &SplitRDB) -> error::Result<HashSet<(u32, Vec<u8>)>> {
+    let file = File::open(path).map_err(|e| RmergerError::from(e).in_file(path))?;
+    read_input_file(&file, |s| -> error::Result<HashSet<(u32, Vec<u8>)>> {
+        let r = parse_input(s, skip_unsupported)?;
+        srdb.record_keys(&r, target_db)
+    }).and_then(|r| r).map_err(|e| e.in_file(path))
+}
+
+/// leave the keys of input file `path` out of the merge, for --difference
+fn exclude_keys(path: &str, target_db: &HashSet<u32>, skip_unsupported: bool, srdb: &mut DiffRDB) -> error::Result<()> {
+    let file = File::open(path).map_err(|e| RmergerError::from(e).in_file(path))?;
+    read_input_file(&file, |s| -> error::Result<()> {
+        let r = parse_input(s, skip_unsupported)?;
+        srdb.exclude(&r, target_db)
+    }).and_then(|r| r).map_err(|e| e.in_file(path))
+}
+
+
+/// write the records of the merged RDB file into the Redis server `target`,
+/// failing after the last record if the server rejected any command
+fn replay(rdb_path: &Path, target: &ReplayTarget) -> Result<(), Box<dyn Error>> {
+    let file = File::open(rdb_path)?;
+    let errors = read_input_file(&file, |s| -> error::Result<Vec<String>> {
+        let r = parse_rdb(s)?;
+        let mut replayer = RedisReplayer::connect(target)?;
+        for (num, record) in &r {
+            replayer.write_record(num.1, record)?;
+        }
+        replayer.finish()
+    }).and_then(|r| r).map_err(|e| e.in_file(&rdb_path.to_string_lossy()))?;
+    if !errors.is_empty() {
+        return Err(format!("{} commands failed on {}:{}", errors.len(), target.host, target.port).into());
+    }
+    Ok(())
+}
+
+/// convert the merged RDB file into `format`, next to it
+fn export(rdb_path: &Path, format: &str) -> Result<PathBuf, Box<dyn Error>> {
+    let path = rdb_path.with_extension(format);
+    let file = File::open(rdb_path)?;
+    memory_map_read(&file, |s| -> Result<(), Box<dyn Error>> {
+        match parse_rdb(s) {
+            Ok(r) => {
+                let out = File::create(&path)?;
+                let records = (&r).into_iter().map(|(num, r)| (num.1, r));
+                match format {
+                    "json" => {
+                        let mut writer = RecordJsonWriter::new(out)?;
+                        for (num, record) in records {
+                            writer.write_record(num, record)?;
+                        }
+                        writer.finish()?;
+                    },
+                    "resp" => {
+                        let mut writer = RespCommandWriter::new(out);
+                        for (num, record) in records {
+                            writer.write_record(num, record)?;
+                        }
+                        writer.finish()?;
+                    },
+                    "commands" => {
+                        let mut writer = TextCommandWriter::new(out);
+                        for (num, record) in records {
+                            writer.write_record(num, record)?;
+                        }
+                        writer.finish()?;
+                    },
+                    _ => {
+                        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis() as u64;
+                        let mut writer = RestoreCommandWriter::new(out);
+                        for (num, record) in records {
+                            writer.write_record(num, record, now_ms)?;
+                        }
+                        writer.finish()?;
+                    },
+                }
+                Ok(())
+            },
+            Err(e) => Err(Box::new(e.in_file(&rdb_path.to_string_lossy()))),
+        }
+    })??;
+    Ok(path)
+}
+
+
+/// PREFIX and OUTFILE.rdb of --split-by-prefix PREFIX[:OUTFILE], split at the last colon
+fn prefix_route(spec: &str) -> (String, String) {
+    match spec.rfind(':') {
+        Some(i) if i + 1 < spec.len() => (spec[..i].to_string(), format!("{}.rdb", &spec[i + 1..])),
+        _ => {
+            let name: String = spec.chars().map(|c| if c.is_ascii_alphanumeric() { c } else { '_' }).collect();
+            (spec.to_string(), format!("MERGE_{}.rdb", name))
+        },
+    }
+}
+
+
+/// F of --sample-fraction
+fn parse_fraction(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(f) if (0.0..=1.0).contains(&f) => Ok(f),
+        _ => Err(format!("not a fraction between 0.0 and 1.0: {}", s)),
+    }
+}
+
+/// RATE of --bloom-error-rate
+fn parse_error_rate(s: &str) -> Result<f64, String> {
+    match s.parse::<f64>() {
+        Ok(f) if f > 0.0 && f < 1.0 => Ok(f),
+        _ => Err(format!("not a rate between 0.0 and 1.0 (exclusive): {}", s)),
+    }
+}
+
+/// SRC:DST of --db-map
+fn parse_db_mapping(s: &str) -> Result<(u32, u32), String> {
+    let pair: Vec<&str> = s.splitn(2, ':').collect();
+    match (pair.first().map(|s| s.parse()), pair.get(1).map(|s| s.parse())) {
+        (Some(Ok(src)), Some(Ok(dst))) => Ok((src, dst)),
+        _ => Err(format!("invalid database mapping: {}", s)),
+    }
 }
 
 
-fn database_set(strs: Vec<String>) -> Result<HashSet<u32>, std::num::ParseIntError> {
-    let mut set = HashSet::new();
-    for i in strs {
-        set.insert(i.parse()?);
+fn database_map(pairs: &[(u32, u32)]) -> error::Result<HashMap<u32, u32>> {
+    let mut map = HashMap::new();
+    for &(src, dst) in pairs {
+        if map.insert(src, dst).is_some() {
+            return Err(RmergerError::DuplicateDatabase(src));
+        }
     }
-    Ok(set)
+    Ok(map)
 }
@@ -1,23 +1,99 @@
-use nom::*;
+use nom::{ IResult, Err, Needed };
+use nom::branch::alt;
+use nom::bytes::streaming::{ tag, take };
+use nom::combinator::{ eof, map, map_opt, map_res, opt, peek, verify };
+use nom::error::{ context, ErrorKind, ParseError, VerboseError };
+use nom::multi::{ count, many0 };
+use nom::number::streaming::{ be_u8, be_u16, be_u32, be_u64 };
+use nom::sequence::{ pair, preceded, tuple };
 use std::io::{
     Write,
     Result as IoResult,
-    Error as IoError,
-    ErrorKind as IoErrorKind,
 };
+use std::cell::Cell;
+use std::cmp::Ordering;
+use std::convert::TryFrom;
+use std::fmt;
+use std::slice;
+
+use super::error::{ RmergerError, Result };
+
+/// value type of a record, as its byte in an RDB file
+#[repr(u8)]
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Clone, Copy)]
+pub enum ValueType {
+    String           = 0x00,
+    List             = 0x01,
+    Set              = 0x02,
+    Sortedset        = 0x03,
+    Hashmap          = 0x04,
+    Module2          = 0x07,
+    //Zipmap           = 0x09, // deprecated (>= RDB v4)
+    Ziplist          = 0x0a,
+    Intset           = 0x0b,
+    SortedsetZiplist = 0x0c,
+    HashmapZiplist   = 0x0d,
+}
+
+/// byte of a value type which is none of `ValueType`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct UnknownValueType(pub u8);
+
+impl fmt::Display for UnknownValueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unknown value type: 0x{:02x}", self.0)
+    }
+}
 
-bitflags! {
-    struct ValueType: u8 {
-        const VT_STRING            = 0x00;
-        const VT_LIST              = 0x01;
-        const VT_SET               = 0x02;
-        const VT_SORTEDSET         = 0x03;
-        const VT_HASHMAP           = 0x04;
-        //const VT_ZIPMAP            = 0x09; // deprecated (>= RDB v4)
-        const VT_ZIPLIST           = 0x0a;
-        const VT_INTSET            = 0x0b;
-        const VT_SORTEDSET_ZIPLIST = 0x0c;
-        const VT_HASHMAP_ZIPLIST   = 0x0d;
+impl ::std::error::Error for UnknownValueType {}
+
+impl TryFrom<u8> for ValueType {
+    type Error = UnknownValueType;
+
+    fn try_from(t: u8) -> ::std::result::Result<Self, UnknownValueType> {
+        match t {
+            0x00 => Ok(ValueType::String),
+            0x01 => Ok(ValueType::List),
+            0x02 => Ok(ValueType::Set),
+            0x03 => Ok(ValueType::Sortedset),
+            0x04 => Ok(ValueType::Hashmap),
+            0x07 => Ok(ValueType::Module2),
+            0x0a => Ok(ValueType::Ziplist),
+            0x0b => Ok(ValueType::Intset),
+            0x0c => Ok(ValueType::SortedsetZiplist),
+            0x0d => Ok(ValueType::HashmapZiplist),
+            t    => Err(UnknownValueType(t)),
+        }
+    }
+}
+
+impl ValueType {
+    /// lowest RDB version which has the value type
+    pub fn min_rdb_version(&self) -> u32 {
+        match *self {
+            ValueType::String | ValueType::List | ValueType::Set | ValueType::Sortedset | ValueType::Hashmap => 1,
+            ValueType::Ziplist | ValueType::Intset | ValueType::SortedsetZiplist => 2,
+            ValueType::HashmapZiplist => 4,
+            ValueType::Module2        => 8,
+        }
+    }
+}
+
+impl fmt::Display for ValueType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            ValueType::String           => "string",
+            ValueType::List             => "list",
+            ValueType::Set              => "set",
+            ValueType::Sortedset        => "sortedset",
+            ValueType::Hashmap          => "hashmap",
+            ValueType::Module2          => "module",
+            ValueType::Ziplist          => "ziplist",
+            ValueType::Intset           => "intset",
+            ValueType::SortedsetZiplist => "sortedset-ziplist",
+            ValueType::HashmapZiplist   => "hashmap-ziplist",
+        };
+        f.pad(name)
     }
 }
 
@@ -37,28 +113,51 @@ pub enum EncodedString<'a> {
 use self::EncodedString::*;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct EncodedList<'a>(EncodedLength<'a>, Vec<EncodedString<'a>>);
+pub struct EncodedList<'a>(pub EncodedLength<'a>, pub Vec<EncodedString<'a>>);
+
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct EncodedSet<'a>(pub EncodedLength<'a>, pub Vec<EncodedString<'a>>);
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct EncodedSet<'a>(EncodedLength<'a>, Vec<EncodedString<'a>>);
+pub struct EncodedSortedset<'a>(pub EncodedLength<'a>, pub Vec<(EncodedString<'a>, u8, &'a [u8])>);
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct EncodedSortedset<'a>(EncodedLength<'a>, Vec<(EncodedString<'a>, u8, &'a [u8])>);
+pub struct EncodedHashmap<'a>(pub EncodedLength<'a>, pub Vec<(EncodedString<'a>, EncodedString<'a>)>);
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct EncodedHashmap<'a>(EncodedLength<'a>, Vec<(EncodedString<'a>, EncodedString<'a>)>);
+pub struct EncodedZiplist<'a>(pub EncodedString<'a>);
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct EncodedZiplist<'a>(EncodedString<'a>);
+pub struct EncodedIntset<'a>(pub EncodedString<'a>);
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct EncodedIntset<'a>(EncodedString<'a>);
+pub struct EncodedSortedsetZiplist<'a>(pub EncodedString<'a>);
 
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct EncodedSortedsetZiplist<'a>(EncodedString<'a>);
+pub struct EncodedHashmapZiplist<'a>(pub EncodedString<'a>);
 
+/// value of a module type: its 64-bit module type id, and the bytes of the id and the opcodes
+/// of the value up to the EOF opcode, which are not decoded
 #[derive(Debug, PartialEq, Eq, Clone)]
-pub struct EncodedHashmapZiplist<'a>(EncodedString<'a>);
+pub struct EncodedModule<'a>(pub u64, pub &'a [u8]);
+
+const MODULE_NAME_CHARSET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789-_";
+
+/// name of a module type, 9 characters encoded in the upper 54 bits of its id
+pub fn module_name(id: u64) -> String {
+    (0..9).rev().map(|i| MODULE_NAME_CHARSET[(id >> (10 + 6 * i) & 63) as usize] as char).collect()
+}
+
+impl<'a> EncodedModule<'a> {
+    pub fn name(&self) -> String {
+        module_name(self.0)
+    }
+
+    /// encoding version of the module type, the lower 10 bits of its id
+    pub fn encoding_version(&self) -> u64 {
+        self.0 & 0x3ff
+    }
+}
 
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum EncodedValue<'a> {
@@ -67,6 +166,7 @@ pub enum EncodedValue<'a> {
     V2(EncodedSet<'a>),
     V3(EncodedSortedset<'a>),
     V4(EncodedHashmap<'a>),
+    V7(EncodedModule<'a>),
     VA(EncodedZiplist<'a>),
     VB(EncodedIntset<'a>),
     VC(EncodedSortedsetZiplist<'a>),
@@ -90,14 +190,27 @@ pub struct DatabaseNumber<'a>(pub EncodedLength<'a>, pub u32);
 #[derive(Debug, PartialEq)]
 pub struct Database<'a>(pub DatabaseNumber<'a>, pub Vec<Record<'a>>);
 
+/// the 4 ASCII digits of the version in the header, ordered by their number
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct RDBVersion(pub [u8; 4]);
+
+/// auxiliary field (key, value) of the header, since RDB v7
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub struct RDBVersion<'a>(pub &'a [u8]);
+pub struct AuxField<'a>(pub EncodedString<'a>, pub EncodedString<'a>);
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
-pub struct Checksum<'a>(&'a [u8]);
+pub struct Checksum<'a>(pub &'a [u8]);
 
 #[derive(Debug, PartialEq)]
-pub struct RDB<'a>(pub RDBVersion<'a>, pub Vec<Database<'a>>, pub Option<Checksum<'a>>);
+pub struct RDB<'a>(pub RDBVersion, pub Vec<AuxField<'a>>, pub Vec<Database<'a>>, pub Option<Checksum<'a>>);
+
+/// record holding its own serialized bytes, so that it can outlive the parsed buffer
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OwnedRecord(Vec<u8>);
+
+/// value holding its own serialized bytes after its value type, so that it can outlive the parsed buffer
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct OwnedValue(Vec<u8>);
 
 
 /// convert function into unsigned int
@@ -110,59 +223,712 @@ impl<'a> From<EncodedLength<'a>> for u32 {
     }
 }
 
+/// encode unsigned int into the shortest length encoding
+pub fn encode_length(n: u32) -> Vec<u8> {
+    if n < 1 << 6 {
+        vec![n as u8]
+    } else if n < 1 << 14 {
+        vec![0x40 | (n >> 8) as u8, n as u8]
+    } else {
+        vec![0x80, (n >> 24) as u8, (n >> 16) as u8, (n >> 8) as u8, n as u8]
+    }
+}
+
+impl<'a> EncodedValue<'a> {
+    pub fn value_type(&self) -> ValueType {
+        match self {
+            &V0(_) => ValueType::String,
+            &V1(_) => ValueType::List,
+            &V2(_) => ValueType::Set,
+            &V3(_) => ValueType::Sortedset,
+            &V4(_) => ValueType::Hashmap,
+            &V7(_) => ValueType::Module2,
+            &VA(_) => ValueType::Ziplist,
+            &VB(_) => ValueType::Intset,
+            &VC(_) => ValueType::SortedsetZiplist,
+            &VD(_) => ValueType::HashmapZiplist,
+        }
+    }
+
+    /// copy the value out of the parsed buffer
+    pub fn to_owned(&self) -> OwnedValue {
+        OwnedValue::from(self)
+    }
+}
+
+impl<'a> Record<'a> {
+    /// copy the record out of the parsed buffer
+    pub fn to_owned(&self) -> OwnedRecord {
+        OwnedRecord::from(self)
+    }
+}
+
+/// RDB versions which can be parsed; a newer version may change the layout of the file itself
+pub const MIN_RDB_VERSION: u32 = 1;
+pub const MAX_RDB_VERSION: u32 = 12;
+/// aux fields were introduced in RDB v7
+pub const AUX_RDB_VERSION: u32 = 7;
+/// module aux data was introduced in RDB v9
+pub const MODULE_AUX_RDB_VERSION: u32 = 9;
+/// function libraries were introduced in RDB v10
+pub const FUNCTION_RDB_VERSION: u32 = 10;
+
+impl RDBVersion {
+    /// version number of the header, e.g. 6 for "0006"
+    pub fn number(&self) -> Result<u32> {
+        let digits = ::std::str::from_utf8(&self.0).ok().filter(|s| s.bytes().all(|b| b.is_ascii_digit()));
+        digits.and_then(|s| s.parse().ok())
+            .ok_or_else(|| RmergerError::Parse(format!("invalid RDB version: {}", String::from_utf8_lossy(&self.0))))
+    }
+
+    /// the version number, or an error if it is not a number between `MIN_RDB_VERSION` and `MAX_RDB_VERSION`
+    pub fn supported(&self) -> Result<u32> {
+        let n = self.number()?;
+        assert_result!((MIN_RDB_VERSION..=MAX_RDB_VERSION).contains(&n), RmergerError::UnsupportedVersion(n));
+        Ok(n)
+    }
+
+    /// whether the version is at least `n`
+    pub fn since(&self, n: u32) -> bool {
+        self.number().is_ok_and(|v| v >= n)
+    }
+}
+
+/// versions which are not a number come first, by their bytes
+impl Ord for RDBVersion {
+    fn cmp(&self, other: &Self) -> Ordering {
+        (self.number().ok(), self.0).cmp(&(other.number().ok(), other.0))
+    }
+}
+
+impl PartialOrd for RDBVersion {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// version `n` zero-padded to 4 digits; an error above 9999
+impl TryFrom<u32> for RDBVersion {
+    type Error = RmergerError;
+
+    fn try_from(n: u32) -> Result<Self> {
+        assert_result!(n <= 9999, RmergerError::InvalidArgument(format!("RDB version out of range: {}", n)));
+        let mut v = [0; 4];
+        v.copy_from_slice(format!("{:04}", n).as_bytes());
+        Ok(RDBVersion(v))
+    }
+}
+
+impl<'a> Database<'a> {
+    pub fn number(&self) -> u32 {
+        (self.0).1
+    }
+
+    pub fn records<'b>(&'b self) -> impl Iterator<Item = &'b Record<'a>> {
+        self.1.iter()
+    }
+}
+
+impl<'a> RDB<'a> {
+    pub fn databases<'b>(&'b self) -> impl Iterator<Item = &'b Database<'a>> {
+        self.2.iter()
+    }
+
+    /// records of database `db` only
+    pub fn iter_db(&'a self, db: u32) -> RDBIter<'a> {
+        RDBIter { db: Some(db), ..self.into_iter() }
+    }
+}
+
+/// records of an RDB with their database number, in file order
+pub struct RDBIter<'a> {
+    db_iter:     slice::Iter<'a, Database<'a>>,
+    record_iter: Option<(DatabaseNumber<'a>, slice::Iter<'a, Record<'a>>)>,
+    db:          Option<u32>,
+    value_type:  Option<ValueType>,
+}
+
+impl<'a> RDBIter<'a> {
+    /// skip the records whose value is not of type `t`
+    pub fn filter_type(self, t: ValueType) -> Self {
+        RDBIter { value_type: Some(t), ..self }
+    }
+}
+
+impl<'a> Iterator for RDBIter<'a> {
+    type Item = (DatabaseNumber<'a>, &'a Record<'a>);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some((num, ref mut records)) = self.record_iter {
+                let t = self.value_type;
+                if let Some(r) = records.find(|r| t.is_none_or(|t| r.1.value_type() == t)) {
+                    return Some((num, r));
+                }
+            }
+            let db = self.db;
+            let &Database(num, ref records) = self.db_iter.find(|d| db.is_none_or(|n| d.number() == n))?;
+            self.record_iter = Some((num, records.iter()));
+        }
+    }
+}
+
+impl<'a> IntoIterator for &'a RDB<'a> {
+    type Item = (DatabaseNumber<'a>, &'a Record<'a>);
+    type IntoIter = RDBIter<'a>;
+
+    fn into_iter(self) -> RDBIter<'a> {
+        RDBIter { db_iter: self.2.iter(), record_iter: None, db: None, value_type: None }
+    }
+}
+
+/// length, or the name of a special encoding
+///
+/// ```
+/// use rmerger::parser::EncodedLength;
+///
+/// assert_eq!(EncodedLength::I(300, &[0x41, 0x2c]).to_string(), "300");
+/// assert_eq!(EncodedLength::S(3, &[0xc3]).to_string(), "lzf");
+/// ```
+impl<'a> fmt::Display for EncodedLength<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &I(n, _) => write!(f, "{}", n),
+            &S(0, _) => f.write_str("int8"),
+            &S(1, _) => f.write_str("int16"),
+            &S(2, _) => f.write_str("int32"),
+            &S(3, _) => f.write_str("lzf"),
+            &S(t, _) => write!(f, "special {}", t),
+        }
+    }
+}
+
+/// decoded string, invalid UTF-8 replaced by U+FFFD
+///
+/// ```
+/// use rmerger::parser::{ EncodedLength, EncodedString };
+///
+/// assert_eq!(EncodedString::Raw(EncodedLength::I(3, &[0x03]), b"foo").to_string(), "foo");
+/// assert_eq!(EncodedString::Int(EncodedLength::S(0, &[0xc0]), &[0xfb]).to_string(), "-5");
+/// ```
+impl<'a> fmt::Display for EncodedString<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match String::decode(self) {
+            Ok(s)  => f.write_str(&s),
+            Err(e) => write!(f, "<{}>", e),
+        }
+    }
+}
+
+fn write_list<T, F>(f: &mut fmt::Formatter, open: &str, items: &[T], close: &str, item: F) -> fmt::Result
+    where F: Fn(&mut fmt::Formatter, &T) -> fmt::Result
+{
+    f.write_str(open)?;
+    for (i, t) in items.iter().enumerate() {
+        if i > 0 {
+            f.write_str(", ")?;
+        }
+        item(f, t)?;
+    }
+    f.write_str(close)
+}
+
+/// strings as they are, lists and sets as `[a, b]`, sorted sets as `{a: 1, b: 2}`, hashes as `{f: v}`
+/// and the compact encodings, which are not expanded, as `<type>`
+///
+/// ```
+/// use rmerger::parser::{ EncodedLength, EncodedString, EncodedValue, EncodedList };
+///
+/// let (a, b) = (EncodedString::Raw(EncodedLength::I(1, &[0x01]), b"a"), EncodedString::Raw(EncodedLength::I(1, &[0x01]), b"b"));
+/// let list = EncodedValue::V1(EncodedList(EncodedLength::I(2, &[0x02]), vec![a, b]));
+/// assert_eq!(list.to_string(), "[a, b]");
+/// ```
+impl<'a> fmt::Display for EncodedValue<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            &V0(ref s) => write!(f, "{}", s),
+            &V1(EncodedList(_, ref v)) | &V2(EncodedSet(_, ref v)) => {
+                write_list(f, "[", v, "]", |f, s| write!(f, "{}", s))
+            },
+            &V3(EncodedSortedset(_, ref v)) => {
+                write_list(f, "{", v, "}", |f, &(ref member, _, score)| {
+                    write!(f, "{}: {}", member, String::from_utf8_lossy(score))
+                })
+            },
+            &V4(EncodedHashmap(_, ref v)) => {
+                write_list(f, "{", v, "}", |f, &(ref field, ref value)| write!(f, "{}: {}", field, value))
+            },
+            _ => write!(f, "<{}>", self.value_type()),
+        }
+    }
+}
+
+/// year, month and day of the `days`th day since 1970-01-01, after Howard Hinnant's civil_from_days
+///
+/// ```
+/// use rmerger::parser::civil_date;
+///
+/// assert_eq!(civil_date(0), (1970, 1, 1));
+/// assert_eq!(civil_date(19_708), (2023, 12, 17));
+/// ```
+pub fn civil_date(days: u64) -> (u64, u64, u64) {
+    // shifted to eras of 400 years starting on March 1st
+    let z = days + 719_468;
+    let (era, doe) = (z / 146_097, z % 146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = era * 400 + yoe + if month <= 2 { 1 } else { 0 };
+    (year, month, day)
+}
+
+/// ISO-8601 timestamp in UTC
+///
+/// ```
+/// use rmerger::parser::ExpiryTime;
+///
+/// assert_eq!(ExpiryTime::MilliSec(&[0x39, 0x30, 0, 0, 0, 0, 0, 0]).to_string(), "1970-01-01T00:00:12.345Z");
+/// assert_eq!(ExpiryTime::Sec(&[0x00, 0xc5, 0x7e, 0x65]).to_string(), "2023-12-17T09:53:04.000Z");
+/// ```
+impl<'a> fmt::Display for ExpiryTime<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let ms = self.as_millis();
+        let (days, ms_of_day) = (ms / 86_400_000, ms % 86_400_000);
+        let (year, month, day) = civil_date(days);
+        write!(f, "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z", year, month, day,
+               ms_of_day / 3_600_000, ms_of_day / 60_000 % 60, ms_of_day / 1000 % 60, ms_of_day % 1000)
+    }
+}
+
+/// `<key> (<type>)`, followed by ` [expires: <timestamp>]` for a key with expiry
+///
+/// ```
+/// use rmerger::parser::{ record, parse_done };
+///
+/// let r = parse_done(record(&[0x00, 0x01, 0x6b, 0x01, 0x76])).unwrap();
+/// assert_eq!(r.to_string(), "k (string)");
+/// let r = parse_done(record(&[0xfd, 0x00, 0xc5, 0x7e, 0x65, 0x01, 0x01, 0x6c, 0x00])).unwrap();
+/// assert_eq!(r.to_string(), "l (list) [expires: 2023-12-17T09:53:04.000Z]");
+/// ```
+impl<'a> fmt::Display for Record<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let &Record(ref key, ref val, expiry) = self;
+        write!(f, "{} ({})", key, val.value_type())?;
+        if let Some(e) = expiry {
+            write!(f, " [expires: {}]", e)?;
+        }
+        Ok(())
+    }
+}
+
+/// ```
+/// use rmerger::parser::{ EncodedLength, DatabaseNumber };
+///
+/// assert_eq!(DatabaseNumber(EncodedLength::I(3, &[0x03]), 3).to_string(), "database 3");
+/// ```
+impl<'a> fmt::Display for DatabaseNumber<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "database {}", self.1)
+    }
+}
+
+/// ```
+/// use rmerger::parser::{ database, parse_done };
+///
+/// let db = parse_done(database(&[0xfe, 0x01, 0x00, 0x01, 0x6b, 0x01, 0x76, 0xff])).unwrap();
+/// assert_eq!(db.to_string(), "database 1: 1 records");
+/// ```
+impl<'a> fmt::Display for Database<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {} records", self.0, self.1.len())
+    }
+}
+
+/// the 4 version digits, e.g. `0006`
+///
+/// ```
+/// use rmerger::parser::RDBVersion;
+///
+/// assert_eq!(RDBVersion(*b"0006").to_string(), "0006");
+/// ```
+impl fmt::Display for RDBVersion {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&String::from_utf8_lossy(&self.0))
+    }
+}
+
+/// ```
+/// use rmerger::parser::{ rdb, parse_done };
+///
+/// let r = parse_done(rdb(b"REDIS0006\xfe\x00\x00\x01k\x01v\xff\x00\x00\x00\x00\x00\x00\x00\x00")).unwrap();
+/// assert_eq!(r.to_string(), "RDB version 0006: 0 aux fields, 1 databases, 1 records");
+/// ```
+impl<'a> fmt::Display for RDB<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let records: usize = self.2.iter().map(|db| db.1.len()).sum();
+        write!(f, "RDB version {}: {} aux fields, {} databases, {} records", self.0, self.1.len(), self.2.len(), records)
+    }
+}
+
+impl<'a> ExpiryTime<'a> {
+    /// expiry as a unix timestamp in milliseconds (stored little-endian)
+    pub fn as_millis(&self) -> u64 {
+        let le = |v: &[u8]| v.iter().rev().fold(0, |a, b| a << 8 | (*b as u64));
+        match self {
+            &MilliSec(v) => le(v),
+            &Sec(v)      => le(v) * 1000,
+        }
+    }
+
+    /// expiry in milliseconds, which Redis stores as a signed 64-bit integer
+    pub fn checked_millis(&self) -> Result<u64> {
+        let ms = self.as_millis();
+        assert_result!(ms <= i64::MAX as u64, RmergerError::InvalidExpiry);
+        Ok(ms)
+    }
+}
+
+impl<'a> Checksum<'a> {
+    /// CRC64 stored little-endian, 0 if the file was written without checksum
+    pub fn as_u64(&self) -> u64 {
+        self.0.iter().rev().fold(0, |a, b| a << 8 | (*b as u64))
+    }
+}
+
+/// output of a finished parse, or the error which stopped it
+pub fn parse_done<O>(result: PResult<O>) -> Result<O> {
+    match result {
+        Ok((_, o))                                => Ok(o),
+        Err(Err::Error(e)) | Err(Err::Failure(e)) => Err(RmergerError::from(e)),
+        Err(Err::Incomplete(_))                   => Err(RmergerError::ParseIncomplete),
+    }
+}
+
+/// parse the RDB file `input`, an error giving the offset of the element which failed to parse
+pub fn parse_rdb(input: &[u8]) -> Result<RDB<'_>> {
+    check_version(input)?;
+    parse_done(rdb(input)).map_err(|e| located(input, e))
+}
+
+/// `parse_rdb`, but skipping the records of an unsupported value type with a warning, see `rdb_skipping`
+pub fn parse_rdb_skipping(input: &[u8]) -> Result<RDB<'_>> {
+    check_version(input)?;
+    let mut skipped = Vec::new();
+    let r = parse_done(rdb_skipping(input, &mut skipped)).map_err(|e| located(input, e))?;
+    for s in skipped {
+        warn!("{}", s);
+    }
+    Ok(r)
+}
+
+// the error of a version in the header which `rdb_version` rejects, rather than the parse error
+fn check_version(input: &[u8]) -> Result<()> {
+    if let Ok((_, v)) = rdb_header(input) {
+        v.supported()?;
+    }
+    Ok(())
+}
+
+fn located(input: &[u8], e: RmergerError) -> RmergerError {
+    let offset = error_offset(input);
+    RmergerError::ParseErrorAt { offset, dump: hex_dump(input, offset), error: Box::new(e) }
+}
+
+/// offset of the element of the RDB file `input` (header, aux field, database number, record,
+/// end or checksum) at which parsing fails, found by parsing the elements one by one
+pub fn error_offset(input: &[u8]) -> usize {
+    let offset = |rest: &[u8]| input.len() - rest.len();
+    let mut rest = match rdb_version(input) {
+        Ok((rest, _)) => rest,
+        _                      => return 0,
+    };
+    loop {
+        rest = match (record(rest), opcode(rest)) {
+            (Ok((r, _)), _) | (_, Ok((r, _))) => r,
+            _ => break,
+        };
+    }
+    match end_of_rdb(rest) {
+        // a checksum of the wrong length, or bytes after it
+        Ok((r, _)) => offset(r),
+        _                   => offset(rest),
+    }
+}
+
+/// bytes of `input` around `offset` in hex, the one at `offset` in brackets
+pub fn hex_dump(input: &[u8], offset: usize) -> String {
+    let start = offset.saturating_sub(8);
+    let end = input.len().min(offset + 16);
+    let mut bytes: Vec<String> = input[start.min(end)..end].iter().enumerate()
+        .map(|(i, b)| if start + i == offset { format!("[{:02x}]", b) } else { format!("{:02x}", b) })
+        .collect();
+    if offset >= input.len() {
+        bytes.push("[EOF]".to_string());
+    }
+    bytes.join(" ")
+}
+
+impl OwnedRecord {
+    /// borrow the record from the owned bytes
+    pub fn record(&self) -> Record<'_> {
+        match record(&self.0[..]) {
+            Ok((_, r)) => r,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a, 'b> From<&'b Record<'a>> for OwnedRecord {
+    fn from(r: &'b Record<'a>) -> Self {
+        let mut v = Vec::new();
+        r.ser(&mut v).unwrap();
+        OwnedRecord(v)
+    }
+}
+
+impl OwnedValue {
+    /// borrow the value from the owned bytes
+    pub fn value(&self) -> EncodedValue<'_> {
+        match typed_value(&self.0[..]) {
+            Ok((_, v)) => v,
+            _ => unreachable!(),
+        }
+    }
+
+    pub fn value_type(&self) -> ValueType {
+        match ValueType::try_from(self.0[0]) {
+            Ok(t) => t,
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl<'a, 'b> From<&'b EncodedValue<'a>> for OwnedValue {
+    fn from(v: &'b EncodedValue<'a>) -> Self {
+        let mut bytes = vec![v.value_type() as u8];
+        v.ser(&mut bytes).unwrap();
+        OwnedValue(bytes)
+    }
+}
+
 /// decode
 pub trait RDBDec<E> {
-    fn decode(dat: &E) -> IoResult<Self> where Self: Sized;
+    fn decode(dat: &E) -> Result<Self> where Self: Sized;
 }
 
-impl<'a> RDBDec<EncodedString<'a>> for String {
-    fn decode(dat: &EncodedString) -> IoResult<Self> {
+impl<'a> RDBDec<EncodedString<'a>> for Vec<u8> {
+    fn decode(dat: &EncodedString) -> Result<Self> {
         match dat {
-            &Raw(_, r) => Ok(String::from_utf8_lossy(r).to_string()),
-            &Int(_, i) => Ok(i.iter().fold(0, |a, j| a << 8 | (*j as i32)).to_string()),
+            &Raw(_, r) => Ok(r.to_vec()),
+            &Int(_, i) => {
+                // little-endian signed integer of 1, 2 or 4 bytes
+                let shift = 64 - 8 * i.len();
+                let n = i.iter().rev().fold(0, |a, j| a << 8 | (*j as i64));
+                Ok(((n << shift) >> shift).to_string().into_bytes())
+            },
             &Lzf(_, _, _, l) => {
-                let mut out = Vec::new();
+                let mut out: Vec<u8> = Vec::new();
                 let mut i = 0;
-                let mut o = 0;
                 let len = l.len();
 
                 while i < len {
-                    assert_result!(i < len, IoError::new(IoErrorKind::Other, "failed to decode LZF"));
                     let ctrl = l[i] as usize;
                     i+=1;
 
                     if ctrl < (1 << 5) {
                         let literal_len = ctrl + 1;
                         let literal_end = i + literal_len;
-                        assert_result!(literal_end <= len, IoError::new(IoErrorKind::Other, "failed to decode LZF"));
-                        out.write(&l[i..literal_end])?;
-                        o += literal_len;
+                        assert_result!(literal_end <= len, RmergerError::Lzf);
+                        out.extend_from_slice(&l[i..literal_end]);
                         i += literal_len;
                     } else {
                         let mut backref_len = ctrl >> 5;
                         if backref_len == 7 {
-                            assert_result!(i < len, IoError::new(IoErrorKind::Other, "failed to decode LZF"));
-                            backref_len += l[i] as usize + 2;
+                            assert_result!(i < len, RmergerError::Lzf);
+                            backref_len += l[i] as usize;
                             i += 1;
                         }
+                        backref_len += 2;
 
-                        assert_result!(i < len, IoError::new(IoErrorKind::Other, "failed to decode LZF"));
-                        let backref_start = o - ((ctrl & 0x1f) << 8) - (l[i] as usize) - 1;
+                        assert_result!(i < len, RmergerError::Lzf);
+                        let offset = ((ctrl & 0x1f) << 8) + (l[i] as usize) + 1;
+                        let backref_start = out.len().checked_sub(offset).ok_or(RmergerError::Lzf)?;
                         i += 1;
                         for j in backref_start..(backref_start+backref_len) {
-                            let buf = [out[j]];
-                            out.write(&buf[..])?;
-                            o += 1;
+                            let b = out[j];
+                            out.push(b);
                         }
                     }
                 }
 
-                Ok(String::from_utf8_lossy(&out[..]).to_string())
+                Ok(out)
             }
         }
     }
 }
 
+impl<'a> RDBDec<EncodedString<'a>> for String {
+    fn decode(dat: &EncodedString) -> Result<Self> {
+        let v: Vec<u8> = RDBDec::decode(dat)?;
+        Ok(String::from_utf8_lossy(&v[..]).to_string())
+    }
+}
+
+/// members of an intset: {element size} {length} {elements}, little-endian integers of 2, 4 or 8 bytes
+impl<'a> RDBDec<EncodedIntset<'a>> for Vec<i64> {
+    fn decode(dat: &EncodedIntset) -> Result<Self> {
+        let b: Vec<u8> = RDBDec::decode(&dat.0)?;
+        let le = |s: &[u8]| s.iter().rev().fold(0, |a, j| a << 8 | (*j as u64));
+        assert_result!(b.len() >= 8, RmergerError::Parse("truncated intset header".to_string()));
+        let (size, len) = (le(&b[0..4]) as usize, le(&b[4..8]) as usize);
+        assert_result!(size == 2 || size == 4 || size == 8,
+                       RmergerError::Parse(format!("invalid intset element size: {}", size)));
+        assert_result!(len.checked_mul(size) == Some(b.len() - 8),
+                       RmergerError::Parse(format!("intset of {} elements of {} bytes in {} bytes", len, size, b.len() - 8)));
+        let shift = 64 - 8 * size;
+        Ok(b[8..].chunks(size).map(|e| ((le(e) << shift) as i64) >> shift).collect())
+    }
+}
+
+// entries of a ziplist: {zlbytes} {zltail} {zllen} {entry}... 0xFF, each entry {prevlen} {encoding} {data},
+// integers of the encoding or the data as their decimal strings
+fn ziplist_entries(s: &EncodedString) -> Result<Vec<Vec<u8>>> {
+    let b: Vec<u8> = RDBDec::decode(s)?;
+    let truncated = || RmergerError::Parse("truncated ziplist".to_string());
+    let le = |s: &[u8]| s.iter().rev().fold(0, |a, j| a << 8 | (*j as u64));
+    assert_result!(b.len() >= 11, truncated());
+    let len = le(&b[8..10]) as usize;
+    let mut entries = Vec::new();
+    let mut rest = &b[10..];
+    loop {
+        let first = *rest.first().ok_or_else(truncated)?;
+        if first == 0xff {
+            break;
+        }
+        // length of the previous entry, in 1 byte or 0xFE and 4 bytes
+        rest = rest.get(if first == 0xfe { 5 } else { 1 }..).ok_or_else(truncated)?;
+        let enc = *rest.first().ok_or_else(truncated)?;
+        // bytes of the encoding, bytes of the data, and whether the data is an integer
+        let (header, n, int) = match enc >> 6 {
+            0b00 => (1, (enc & 0x3f) as usize, false),
+            0b01 => (2, ((enc & 0x3f) as usize) << 8 | *rest.get(1).ok_or_else(truncated)? as usize, false),
+            0b10 => {
+                let n = rest.get(1..5).ok_or_else(truncated)?;
+                (5, n.iter().fold(0, |a, j| a << 8 | *j as usize), false)
+            },
+            _ => match enc {
+                0xc0 => (1, 2, true),
+                0xd0 => (1, 4, true),
+                0xe0 => (1, 8, true),
+                0xf0 => (1, 3, true),
+                0xfe => (1, 1, true),
+                0xf1..=0xfd => (1, 0, true),
+                _ => return Err(RmergerError::Parse(format!("invalid ziplist entry encoding: 0x{:02x}", enc))),
+            },
+        };
+        let data = rest.get(header..header + n).ok_or_else(truncated)?;
+        entries.push(if !int {
+            data.to_vec()
+        } else if n == 0 {
+            // 1111xxxx: xxxx - 1, from 0 to 12
+            ((enc & 0x0f) - 1).to_string().into_bytes()
+        } else {
+            let shift = 64 - 8 * n;
+            (((le(data) << shift) as i64) >> shift).to_string().into_bytes()
+        });
+        rest = &rest[header + n..];
+    }
+    // a length of 0xFFFF means that the entries have to be counted
+    assert_result!(len == 0xffff || len == entries.len(),
+                   RmergerError::Parse(format!("ziplist of {} entries with {} entries", len, entries.len())));
+    Ok(entries)
+}
+
+// consecutive entries of a ziplist as pairs
+fn ziplist_pairs(s: &EncodedString) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    let entries = ziplist_entries(s)?;
+    assert_result!(entries.len().is_multiple_of(2), RmergerError::Parse(format!("ziplist of pairs with {} entries", entries.len())));
+    let mut entries = entries.into_iter();
+    Ok(::std::iter::from_fn(|| Some((entries.next()?, entries.next()?))).collect())
+}
+
+/// ziplist of `elements`, each stored as an integer when it is the decimal string of one, as Redis does
+pub fn ziplist_encode(elements: &[&[u8]]) -> Vec<u8> {
+    let mut entries = Vec::new();
+    let mut prevlen = 0;
+    let mut tail = 10;
+    for &s in elements {
+        let start = entries.len();
+        if prevlen < 0xfe {
+            entries.push(prevlen as u8);
+        } else {
+            entries.push(0xfe);
+            entries.extend_from_slice(&(prevlen as u32).to_le_bytes());
+        }
+        let int = std::str::from_utf8(s).ok().filter(|_| s.len() < 32)
+            .and_then(|s| s.parse::<i64>().ok().filter(|n| n.to_string() == s));
+        match int {
+            Some(n @ 0..=12)                       => entries.push(0xf1 + n as u8),
+            Some(n) if i8::try_from(n).is_ok()     => { entries.push(0xfe); entries.push(n as u8) },
+            Some(n) if i16::try_from(n).is_ok()    => { entries.push(0xc0); entries.extend_from_slice(&(n as i16).to_le_bytes()) },
+            Some(n) if (-0x80_0000..0x80_0000).contains(&n) => { entries.push(0xf0); entries.extend_from_slice(&(n as i32).to_le_bytes()[..3]) },
+            Some(n) if i32::try_from(n).is_ok()    => { entries.push(0xd0); entries.extend_from_slice(&(n as i32).to_le_bytes()) },
+            Some(n)                                => { entries.push(0xe0); entries.extend_from_slice(&n.to_le_bytes()) },
+            None => {
+                if s.len() <= 0x3f {
+                    entries.push(s.len() as u8);
+                } else if s.len() <= 0x3fff {
+                    entries.push(0x40 | (s.len() >> 8) as u8);
+                    entries.push(s.len() as u8);
+                } else {
+                    entries.push(0x80);
+                    entries.extend_from_slice(&(s.len() as u32).to_be_bytes());
+                }
+                entries.extend_from_slice(s);
+            },
+        }
+        tail = 10 + start;
+        prevlen = entries.len() - start;
+    }
+    // {zlbytes} {zltail} {zllen} {entry}... 0xFF, a length of 0xFFFF for 65535 entries or more
+    let mut zl = Vec::with_capacity(entries.len() + 11);
+    zl.extend_from_slice(&((entries.len() + 11) as u32).to_le_bytes());
+    zl.extend_from_slice(&(tail as u32).to_le_bytes());
+    zl.extend_from_slice(&(elements.len().min(0xffff) as u16).to_le_bytes());
+    zl.extend(entries);
+    zl.push(0xff);
+    zl
+}
+
+/// elements of a ziplist encoded list
+impl<'a> RDBDec<EncodedZiplist<'a>> for Vec<Vec<u8>> {
+    fn decode(dat: &EncodedZiplist) -> Result<Self> {
+        ziplist_entries(&dat.0)
+    }
+}
+
+/// (member, score) pairs of a ziplist encoded sorted set, the scores as decimal strings
+impl<'a> RDBDec<EncodedSortedsetZiplist<'a>> for Vec<(Vec<u8>, Vec<u8>)> {
+    fn decode(dat: &EncodedSortedsetZiplist) -> Result<Self> {
+        ziplist_pairs(&dat.0)
+    }
+}
+
+/// (field, value) pairs of a ziplist encoded hash
+impl<'a> RDBDec<EncodedHashmapZiplist<'a>> for Vec<(Vec<u8>, Vec<u8>)> {
+    fn decode(dat: &EncodedHashmapZiplist) -> Result<Self> {
+        ziplist_pairs(&dat.0)
+    }
+}
+
+// write all of `buf`, which a single `write` may not, e.g. into a GzEncoder, and return its length
+fn write_bytes<W: Write>(w: &mut W, buf: &[u8]) -> IoResult<usize> {
+    w.write_all(buf)?;
+    Ok(buf.len())
+}
+
 /// serialize into RDB format
 pub trait RDBSer {
     fn ser<W: Write>(&self, w: &mut W) -> IoResult<usize>;
@@ -177,8 +943,8 @@ pub trait RDBSer {
 impl<'a> RDBSer for EncodedLength<'a> {
     fn ser<W: Write>(&self, w: &mut W) -> IoResult<usize> {
         match self {
-            &I(_, s) => w.write(s),
-            &S(_, s) => w.write(s),
+            &I(_, s) => write_bytes(w, s),
+            &S(_, s) => write_bytes(w, s),
         }
     }
 }
@@ -186,13 +952,13 @@ impl<'a> RDBSer for EncodedLength<'a> {
 impl<'a> RDBSer for EncodedString<'a> {
     fn ser<W: Write>(&self, w: &mut W) -> IoResult<usize> {
         match self {
-            &Raw(s, v) => Ok(s.ser(w)? + w.write(v)?),
-            &Int(s, v) => Ok(s.ser(w)? + w.write(v)?),
+            &Raw(s, v) => Ok(s.ser(w)? + write_bytes(w, v)?),
+            &Int(s, v) => Ok(s.ser(w)? + write_bytes(w, v)?),
             &Lzf(s, t, u, v) => Ok(
                 s.ser(w)? +
                     t.ser(w)? +
                     u.ser(w)? +
-                    w.write(v)?
+                    write_bytes(w, v)?
             ),
         }
     }
@@ -227,8 +993,8 @@ impl<'a> RDBSer for EncodedSortedset<'a> {
         for i in tuples {
             let &(v, u, f) = i;
             n += v.ser(w)?;
-            n += w.write(&[u][..])?;
-            n += w.write(f)?;
+            n += write_bytes(w, &[u][..])?;
+            n += write_bytes(w, f)?;
         }
         Ok(n)
     }
@@ -255,6 +1021,13 @@ impl<'a> RDBSer for EncodedZiplist<'a> {
 }
 
 
+impl<'a> RDBSer for EncodedModule<'a> {
+    fn ser<W: Write>(&self, w: &mut W) -> IoResult<usize> {
+        write_bytes(w, self.1)
+    }
+}
+
+
 impl<'a> RDBSer for EncodedIntset<'a> {
     fn ser<W: Write>(&self, w: &mut W) -> IoResult<usize> {
         let &EncodedIntset(s) = self;
@@ -281,8 +1054,25 @@ impl<'a> RDBSer for EncodedHashmapZiplist<'a> {
 impl<'a> RDBSer for ExpiryTime<'a> {
     fn ser<W: Write>(&self, w: &mut W) -> IoResult<usize> {
         match self {
-            &MilliSec(v) => Ok(w.write(&[0xfc][..])? + w.write(v)?),
-            &Sec(v)      => Ok(w.write(&[0xfd][..])? + w.write(v)?),
+            &MilliSec(v) => Ok(write_bytes(w, &[0xfc][..])? + write_bytes(w, v)?),
+            &Sec(v)      => Ok(write_bytes(w, &[0xfd][..])? + write_bytes(w, v)?),
+        }
+    }
+}
+
+impl<'a> RDBSer for EncodedValue<'a> {
+    fn ser<W: Write>(&self, w: &mut W) -> IoResult<usize> {
+        match self {
+            &V0(ref v) => v.ser(w),
+            &V1(ref v) => v.ser(w),
+            &V2(ref v) => v.ser(w),
+            &V3(ref v) => v.ser(w),
+            &V4(ref v) => v.ser(w),
+            &V7(ref v) => v.ser(w),
+            &VA(ref v) => v.ser(w),
+            &VB(ref v) => v.ser(w),
+            &VC(ref v) => v.ser(w),
+            &VD(ref v) => v.ser(w),
         }
     }
 }
@@ -292,57 +1082,13 @@ impl<'a> RDBSer for Record<'a> {
         let &Record(key, ref val, opt) = self;
         let mut n = 0;
 
-        for exp in opt {
+        if let Some(exp) = opt {
             n += exp.ser(w)?
         }
 
-        match val {
-            &V0(ref v) => {
-                n += w.write(&[VT_STRING.bits()][..])?;
-                n += key.ser(w)?;
-                n += v.ser(w)?;
-            },
-            &V1(ref v) => {
-                n += w.write(&[VT_LIST.bits()][..])?;
-                n += key.ser(w)?;
-                n += v.ser(w)?;
-            },
-            &V2(ref v) => {
-                n += w.write(&[VT_SET.bits()][..])?;
-                n += key.ser(w)?;
-                n += v.ser(w)?;
-            },
-            &V3(ref v) => {
-                n += w.write(&[VT_SORTEDSET.bits()][..])?;
-                n += key.ser(w)?;
-                n += v.ser(w)?;
-            },
-            &V4(ref v) => {
-                n += w.write(&[VT_HASHMAP.bits()][..])?;
-                n += key.ser(w)?;
-                n += v.ser(w)?;
-            },
-            &VA(ref v) => {
-                n += w.write(&[VT_ZIPLIST.bits()][..])?;
-                n += key.ser(w)?;
-                n += v.ser(w)?;
-            },
-            &VB(ref v) => {
-                n += w.write(&[VT_INTSET.bits()][..])?;
-                n += key.ser(w)?;
-                n += v.ser(w)?;
-            },
-            &VC(ref v) => {
-                n += w.write(&[VT_SORTEDSET_ZIPLIST.bits()][..])?;
-                n += key.ser(w)?;
-                n += v.ser(w)?;
-            },
-            &VD(ref v) => {
-                n += w.write(&[VT_HASHMAP_ZIPLIST.bits()][..])?;
-                n += key.ser(w)?;
-                n += v.ser(w)?;
-            },
-        }
+        n += write_bytes(w, &[val.value_type() as u8][..])?;
+        n += key.ser(w)?;
+        n += val.ser(w)?;
         Ok(n)
     }
 }
@@ -350,7 +1096,7 @@ impl<'a> RDBSer for Record<'a> {
 impl<'a> RDBSer for DatabaseNumber<'a> {
     fn ser<W: Write>(&self, w: &mut W) -> IoResult<usize> {
         let &DatabaseNumber(num, _) = self;
-        let mut n = w.write(&[0xfe][..])?;
+        let mut n = write_bytes(w, &[0xfe][..])?;
         n += num.ser(w)?;
         Ok(n)
     }
@@ -367,11 +1113,11 @@ impl<'a> RDBSer for Database<'a> {
     }
 }
 
-impl<'a> RDBSer for RDBVersion<'a> {
+impl RDBSer for RDBVersion {
     fn ser<W: Write>(&self, w: &mut W) -> IoResult<usize> {
         let &RDBVersion(v) = self;
-        let mut n = w.write(b"REDIS")?;
-        n += w.write(v)?;
+        let mut n = write_bytes(w, b"REDIS")?;
+        n += write_bytes(w, &v)?;
         Ok(n)
     }
 }
@@ -379,292 +1125,588 @@ impl<'a> RDBSer for RDBVersion<'a> {
 impl<'a> RDBSer for Checksum<'a> {
     fn ser<W: Write>(&self, w: &mut W) -> IoResult<usize> {
         let &Checksum(v) = self;
-        w.write(v)
+        write_bytes(w, v)
+    }
+}
+
+impl RDBSer for OwnedRecord {
+    fn ser<W: Write>(&self, w: &mut W) -> IoResult<usize> {
+        write_bytes(w, &self.0[..])
+    }
+}
+
+impl RDBSer for OwnedValue {
+    fn ser<W: Write>(&self, w: &mut W) -> IoResult<usize> {
+        write_bytes(w, &self.0[1..])
+    }
+}
+
+impl<'a> RDBSer for AuxField<'a> {
+    fn ser<W: Write>(&self, w: &mut W) -> IoResult<usize> {
+        let &AuxField(key, value) = self;
+        let mut n = write_bytes(w, &[0xfa][..])?;
+        n += key.ser(w)?;
+        n += value.ser(w)?;
+        Ok(n)
     }
 }
 
 impl<'a> RDBSer for RDB<'a> {
     fn ser<W: Write>(&self, w: &mut W) -> IoResult<usize> {
-        let &RDB(v, ref dbs, opt) = self;
+        let &RDB(v, ref aux, ref dbs, opt) = self;
         let mut n = v.ser(w)?;
+        for a in aux {
+            n += a.ser(w)?;
+        }
         for db in dbs {
             n += db.ser(w)?;
         }
-        n += w.write(&[0xff][..])?;
-        for cs in opt {
+        n += write_bytes(w, &[0xff][..])?;
+        if let Some(cs) = opt {
             n += cs.ser(w)?;
         }
         Ok(n)
     }
 }
 
+/// result of the parser combinators, errors keeping the input where they occurred
+pub type PResult<'a, O> = IResult<&'a [u8], O, VerboseError<&'a [u8]>>;
+
+/// error of a parser which does not apply to `input`
+fn fail<O>(input: &[u8], kind: ErrorKind) -> PResult<'_, O> {
+    Err(Err::Error(VerboseError::from_error_kind(input, kind)))
+}
+
 /// parser combinator
-named!(
-    encoded_length<&[u8], EncodedLength>,
-    switch!(
-        peek!(bits!(pair!(take_bits!(u8, 2), take_bits!(u8, 6)))),
-        (0b00, v) => do_parse!(
-            p: take!(1) >>
-            (I(v as u32, p))
-        ) |
-        (0b01, _) => do_parse!(
-            p: peek!(take!(2)) >>
-            v: be_u16          >>
-            (I(v as u32 & 0x3FFF, p))
-        ) |
-        (0b10, _) => do_parse!(
-            p: peek!(take!(5)) >>
-            take!(1)           >>
-            v: be_u32          >>
-            (I(v, p))
-        ) |
-        (0b11, v) => do_parse!(
-            p: take!(1) >>
-            (S(v, p))
-        )
-    )
-);
-
-named!(
-    value_type<&[u8], ValueType>,
-    map!(
-        bits!(pair!(tag_bits!(u8, 1, 0b0), take_bits!(u8, 7))),
-        |(_, n)| ValueType::from_bits_truncate(n)
-    )
-);
-
-named!(
-    encoded_string<&[u8], EncodedString>,
-    switch!(
-        encoded_length,
-        I(n, s)            => map!(take!(n), |v| Raw(I(n, s), v)) |
-        s@S(0b00000000, _) => map!(take!(1), |v| Int(s, v)) |
-        s@S(0b00000001, _) => map!(take!(2), |v| Int(s, v)) |
-        s@S(0b00000010, _) => map!(take!(4), |v| Int(s, v)) |
-        s@S(0b00000011, _) => do_parse!(
-            t: encoded_length      >>
-            u: encoded_length      >>
-            v: take!(u32::from(t)) >>
-            (Lzf(s, t, u, v))
-        )
-    )
-);
-
-named!(
-    encoded_sequence<&[u8], (EncodedLength, Vec<EncodedString>)>,
-    do_parse!(
-        s: encoded_length                                >>
-        v: count!(encoded_string, u32::from(s) as usize) >>
-        (s, v)
-    )
-);
-
-named!(
-    encoded_list<&[u8], EncodedList>,
-    map!(encoded_sequence, |(s, v)| EncodedList(s, v))
-);
-
-named!(
-    encoded_set<&[u8], EncodedSet>,
-    map!(encoded_sequence, |(s, v)| EncodedSet(s, v))
-);
-
-named!(
-    encoded_sortedset<&[u8], EncodedSortedset>,
-    do_parse!(
-        s: encoded_length >>
-        v: count!(
-           do_parse!(
-               w: encoded_string >>
-               u: be_u8          >>
-               f: take!(u)       >>
-               (w, u, f)
-           ),
-           u32::from(s.clone()) as usize
-        ) >>
-        (EncodedSortedset(s, v))
-    )
-);
-
-named!(
-    encoded_hash<&[u8], EncodedHashmap>,
-    do_parse!(
-        s: encoded_length >>
-        t: count!(pair!(encoded_string, encoded_string), u32::from(s.clone()) as usize) >>
-        (EncodedHashmap(s, t))
-    )
-);
-
-named!(
-    encoded_ziplist<&[u8], EncodedZiplist>,
-    map!(encoded_string, |s| EncodedZiplist(s))
-);
-
-named!(
-    encoded_intset<&[u8], EncodedIntset>,
-    map!(encoded_string, |s| EncodedIntset(s))
-);
-
-named!(
-    encoded_sortedset_ziplist<&[u8], EncodedSortedsetZiplist>,
-    map!(encoded_string, |s| EncodedSortedsetZiplist(s))
-);
-
-named!(
-    encoded_hashmap_ziplist<&[u8], EncodedHashmapZiplist>,
-    map!(encoded_string, |s| EncodedHashmapZiplist(s))
-);
+fn encoded_length(input: &[u8]) -> PResult<'_, EncodedLength<'_>> {
+    let (_, b) = peek(be_u8)(input)?;
+    match b >> 6 {
+        0b00 => map(take(1usize), |p| I((b & 0x3f) as u32, p))(input),
+        0b01 => {
+            let (_, p) = peek(take(2usize))(input)?;
+            let (rest, v) = be_u16(input)?;
+            Ok((rest, I(v as u32 & 0x3fff, p)))
+        },
+        0b10 => match b {
+            0x80 => {
+                let (_, p) = peek(take(5usize))(input)?;
+                let (rest, v) = preceded(take(1usize), be_u32)(input)?;
+                Ok((rest, I(v, p)))
+            },
+            // 64-bit length, only as long as it fits in 32 bits
+            0x81 => {
+                let (_, p) = peek(take(9usize))(input)?;
+                let (rest, v) = preceded(take(1usize), map_opt(be_u64, |v| u32::try_from(v).ok()))(input)?;
+                Ok((rest, I(v, p)))
+            },
+            _ => fail(input, ErrorKind::Switch),
+        },
+        _ => map(take(1usize), |p| S(b & 0x3f, p))(input),
+    }
+}
+
+fn value_type(input: &[u8]) -> PResult<'_, ValueType> {
+    map_res(be_u8, ValueType::try_from)(input)
+}
+
+fn encoded_string(input: &[u8]) -> PResult<'_, EncodedString<'_>> {
+    let (rest, l) = encoded_length(input)?;
+    match l {
+        I(n, _)            => map(take(n), |v| Raw(l, v))(rest),
+        S(0b00000000, _)   => map(take(1usize), |v| Int(l, v))(rest),
+        S(0b00000001, _)   => map(take(2usize), |v| Int(l, v))(rest),
+        S(0b00000010, _)   => map(take(4usize), |v| Int(l, v))(rest),
+        S(0b00000011, _)   => {
+            let (rest, (t, u)) = pair(encoded_length, encoded_length)(rest)?;
+            map(take(u32::from(t)), move |v| Lzf(l, t, u, v))(rest)
+        },
+        _ => fail(input, ErrorKind::Switch),
+    }
+}
+
+fn encoded_sequence(input: &[u8]) -> PResult<'_, (EncodedLength<'_>, Vec<EncodedString<'_>>)> {
+    let (rest, s) = encoded_length(input)?;
+    map(count(encoded_string, u32::from(s) as usize), move |v| (s, v))(rest)
+}
+
+fn encoded_list(input: &[u8]) -> PResult<'_, EncodedList<'_>> {
+    map(encoded_sequence, |(s, v)| EncodedList(s, v))(input)
+}
+
+fn encoded_set(input: &[u8]) -> PResult<'_, EncodedSet<'_>> {
+    map(encoded_sequence, |(s, v)| EncodedSet(s, v))(input)
+}
+
+// {member} {score length} {score as a string}
+fn sortedset_member(input: &[u8]) -> PResult<'_, (EncodedString<'_>, u8, &[u8])> {
+    let (rest, (w, u)) = pair(encoded_string, be_u8)(input)?;
+    map(take(u), move |f| (w, u, f))(rest)
+}
+
+fn encoded_sortedset(input: &[u8]) -> PResult<'_, EncodedSortedset<'_>> {
+    let (rest, s) = encoded_length(input)?;
+    map(count(sortedset_member, u32::from(s) as usize), move |v| EncodedSortedset(s, v))(rest)
+}
+
+fn encoded_hash(input: &[u8]) -> PResult<'_, EncodedHashmap<'_>> {
+    let (rest, s) = encoded_length(input)?;
+    map(count(pair(encoded_string, encoded_string), u32::from(s) as usize), move |t| EncodedHashmap(s, t))(rest)
+}
+
+// length of up to 64 bits, as of module type ids and module integers
+fn encoded_length_u64(input: &[u8]) -> PResult<'_, u64> {
+    match input.first() {
+        Some(&0x81) => preceded(take(1usize), be_u64)(input),
+        _ => map_opt(encoded_length, |l| match l { I(n, _) => Some(u64::from(n)), S(..) => None })(input),
+    }
+}
+
+// {opcode} {value} ... {EOF opcode}, as written by a module with the RedisModule_Save* functions
+fn module_opcodes(input: &[u8]) -> PResult<'_, ()> {
+    let mut rest = input;
+    loop {
+        let (r, opcode) = encoded_length_u64(rest)?;
+        rest = match opcode {
+            0     => return Ok((r, ())),
+            1 | 2 => encoded_length_u64(r)?.0,
+            3     => take(4usize)(r)?.0,
+            4     => take(8usize)(r)?.0,
+            5     => encoded_string(r)?.0,
+            _     => return fail(rest, ErrorKind::Switch),
+        };
+    }
+}
+
+// {module type id} {opcodes}
+fn encoded_module(input: &[u8]) -> PResult<'_, EncodedModule<'_>> {
+    let (rest, id) = encoded_length_u64(input)?;
+    let (rest, _) = module_opcodes(rest)?;
+    Ok((rest, EncodedModule(id, &input[..input.len() - rest.len()])))
+}
+
+// F7 {module type id} {when opcode} {when} {opcodes}, global data of a module
+pub fn module_aux(input: &[u8]) -> PResult<'_, u64> {
+    let (rest, _) = trace_input(input, "module aux")?;
+    let (rest, id) = preceded(tag(&[0xf7][..]), encoded_length_u64)(rest)?;
+    let (rest, _) = tuple((encoded_length_u64, encoded_length_u64, module_opcodes))(rest)?;
+    Ok((rest, id))
+}
+
+fn encoded_ziplist(input: &[u8]) -> PResult<'_, EncodedZiplist<'_>> {
+    map(encoded_string, EncodedZiplist)(input)
+}
+
+fn encoded_intset(input: &[u8]) -> PResult<'_, EncodedIntset<'_>> {
+    map(encoded_string, EncodedIntset)(input)
+}
+
+fn encoded_sortedset_ziplist(input: &[u8]) -> PResult<'_, EncodedSortedsetZiplist<'_>> {
+    map(encoded_string, EncodedSortedsetZiplist)(input)
+}
+
+fn encoded_hashmap_ziplist(input: &[u8]) -> PResult<'_, EncodedHashmapZiplist<'_>> {
+    map(encoded_string, EncodedHashmapZiplist)(input)
+}
 
 // FC {8 bytes unsigned long}
-named!(
-    expiry_time_msec<&[u8], ExpiryTime>,
-    do_parse!(
-        tag!([0xfc]) >>
-        e: take!(8)  >>
-        (MilliSec(e))
-    )
-);
+fn expiry_time_msec(input: &[u8]) -> PResult<'_, ExpiryTime<'_>> {
+    map(preceded(tag(&[0xfc][..]), take(8usize)), MilliSec)(input)
+}
 
 // FD {4 bytes unsigned int}
-named!(
-    expiry_time_sec<&[u8], ExpiryTime>,
-    do_parse!(
-        tag!([0xfd]) >>
-        e: take!(4)  >>
-        (Sec(e))
-    )
-);
-
-named!(
-    pub record<&[u8], Record>,
-    do_parse!(
-        o: opt!(alt!(expiry_time_msec | expiry_time_sec)) >>
-        t: value_type >>
-        k: encoded_string >>
-        v: switch!(
-            value!(t),
-            VT_STRING            => map!(encoded_string,            |v| V0(v)) |
-            VT_LIST              => map!(encoded_list,              |v| V1(v)) |
-            VT_SET               => map!(encoded_set,               |v| V2(v)) |
-            VT_SORTEDSET         => map!(encoded_sortedset,         |v| V3(v)) |
-            VT_HASHMAP           => map!(encoded_hash,              |v| V4(v)) |
-            VT_ZIPLIST           => map!(encoded_ziplist,           |v| VA(v)) |
-            VT_INTSET            => map!(encoded_intset,            |v| VB(v)) |
-            VT_SORTEDSET_ZIPLIST => map!(encoded_sortedset_ziplist, |v| VC(v)) |
-            VT_HASHMAP_ZIPLIST   => map!(encoded_hashmap_ziplist,   |v| VD(v))
-        ) >>
-        (Record(k, v, o))
-    )
-);
+fn expiry_time_sec(input: &[u8]) -> PResult<'_, ExpiryTime<'_>> {
+    map(preceded(tag(&[0xfd][..]), take(4usize)), Sec)(input)
+}
+
+thread_local! {
+    // start address and length of the RDB file being parsed, set by `rdb_version`
+    static FILE_RANGE: Cell<(usize, usize)> = const { Cell::new((0, 0)) };
+}
+
+/// log the position of `what` as its offset in the RDB file, or as the number of bytes left
+/// when `input` is not a part of the file whose header was parsed last
+fn trace_input<'a>(input: &'a [u8], what: &str) -> PResult<'a, ()> {
+    let (start, len) = FILE_RANGE.with(|r| r.get());
+    let offset = (input.as_ptr() as usize).wrapping_sub(start);
+    if offset.checked_add(input.len()) == Some(len) {
+        trace!("{}: offset {}", what, offset);
+    } else {
+        trace!("{}: {} bytes left", what, input.len());
+    }
+    Ok((input, ()))
+}
+
+/// value of type `t`
+fn encoded_value(input: &[u8], t: ValueType) -> PResult<'_, EncodedValue<'_>> {
+    match t {
+        ValueType::String           => map(encoded_string,            V0)(input),
+        ValueType::List             => map(encoded_list,              V1)(input),
+        ValueType::Set              => map(encoded_set,               V2)(input),
+        ValueType::Sortedset        => map(encoded_sortedset,         V3)(input),
+        ValueType::Hashmap          => map(encoded_hash,              V4)(input),
+        ValueType::Module2          => map(encoded_module,            V7)(input),
+        ValueType::Ziplist          => map(encoded_ziplist,           VA)(input),
+        ValueType::Intset           => map(encoded_intset,            VB)(input),
+        ValueType::SortedsetZiplist => map(encoded_sortedset_ziplist, VC)(input),
+        ValueType::HashmapZiplist   => map(encoded_hashmap_ziplist,   VD)(input),
+    }
+}
+
+// {value type} {value}, as held by OwnedValue
+fn typed_value(input: &[u8]) -> PResult<'_, EncodedValue<'_>> {
+    let (rest, t) = value_type(input)?;
+    encoded_value(rest, t)
+}
+
+pub fn record(input: &[u8]) -> PResult<'_, Record<'_>> {
+    let (rest, _) = trace_input(input, "record")?;
+    let (rest, (o, t, k)) = tuple((opt(alt((expiry_time_msec, expiry_time_sec))), value_type, encoded_string))(rest)?;
+    let (rest, v) = encoded_value(rest, t)?;
+    Ok((rest, Record(k, v, o)))
+}
+
+// rest after `n` strings
+fn skip_strings(input: &[u8], n: usize) -> PResult<'_, ()> {
+    let mut rest = input;
+    for _ in 0..n {
+        rest = encoded_string(rest)?.0;
+    }
+    Ok((rest, ()))
+}
+
+// rest after a value of type `t`, walked by its lengths without collecting its elements
+fn skip_encoded_value(input: &[u8], t: ValueType) -> PResult<'_, ()> {
+    match t {
+        ValueType::List | ValueType::Set => {
+            let (rest, n) = encoded_length(input)?;
+            skip_strings(rest, u32::from(n) as usize)
+        },
+        ValueType::Hashmap => {
+            let (rest, n) = encoded_length(input)?;
+            skip_strings(rest, u32::from(n) as usize * 2)
+        },
+        ValueType::Sortedset => {
+            let (mut rest, n) = encoded_length(input)?;
+            for _ in 0..u32::from(n) {
+                rest = sortedset_member(rest)?.0;
+            }
+            Ok((rest, ()))
+        },
+        ValueType::Module2 => map(encoded_module, |_| ())(input),
+        // a string, or a ziplist or intset in one
+        _ => map(encoded_string, |_| ())(input),
+    }
+}
+
+/// value type and key of a record, its expiry and value skipped, for a scan of the keys of a file
+pub fn record_key(input: &[u8]) -> PResult<'_, (ValueType, EncodedString<'_>)> {
+    let (rest, (_, t, k)) = tuple((opt(alt((expiry_time_msec, expiry_time_sec))), value_type, encoded_string))(input)?;
+    let (rest, _) = skip_encoded_value(rest, t)?;
+    Ok((rest, (t, k)))
+}
 
 // FE {length encoding}
-named!(
-    pub database_number<&[u8], DatabaseNumber>,
-    do_parse!(
-        tag!([0xfe])      >>
-        n: encoded_length >>
-        (DatabaseNumber(n, u32::from(n)))
-    )
-);
+pub fn database_number(input: &[u8]) -> PResult<'_, DatabaseNumber<'_>> {
+    let (rest, _) = trace_input(input, "database number")?;
+    map(preceded(tag(&[0xfe][..]), encoded_length), |n| DatabaseNumber(n, u32::from(n)))(rest)
+}
+
+// FB {database size} {expires size}, the hash table sizes for loading after a database number
+// as of RDB v7, which have no place in `Database` and are skipped
+pub fn resize_db(input: &[u8]) -> PResult<'_, ()> {
+    let (rest, _) = trace_input(input, "resize db")?;
+    map(preceded(tag(&[0xfb][..]), pair(encoded_length_u64, encoded_length_u64)), |_| ())(rest)
+}
 
 // FF
-named!(
-    end_of_rdb<&[u8], &[u8]>,
-    tag!([0xff])
-);
-
-named!(
-    checksum<&[u8], Checksum>,
-    map!(take!(8), |v| Checksum(v))
-);
-
-// "REDIS0006"
-named!(
-    rdb_version<&[u8], RDBVersion>,
-    do_parse!(
-        tag!("REDIS") >>
-        v: take!(4)   >>
-        (RDBVersion(v))
-    )
-);
-
-named!(
-    pub database<&[u8], Database>,
-    do_parse!(
-        n: database_number >>
-        r: many0!(record)  >>
-        (Database(n, r))
-    )
-);
-
-named!(
-    pub rdb<&[u8], RDB>,
-    do_parse!(
-        v: rdb_version      >>
-        d: many0!(database) >>
-        end_of_rdb          >>
-        c: opt!(checksum)   >>
-        eof!()              >>
-        (RDB(v, d, c))
-    )
-);
+pub fn end_of_rdb(input: &[u8]) -> PResult<'_, &[u8]> {
+    tag(&[0xff][..])(input)
+}
+
+pub fn checksum(input: &[u8]) -> PResult<'_, Checksum<'_>> {
+    map(take(8usize), Checksum)(input)
+}
 
+// "REDIS0006", of any 4 bytes
+fn rdb_header(input: &[u8]) -> PResult<'_, RDBVersion> {
+    map(preceded(tag("REDIS"), take(4usize)), |v: &[u8]| RDBVersion([v[0], v[1], v[2], v[3]]))(input)
+}
+
+// "REDIS0006", of a supported version
+pub fn rdb_version(input: &[u8]) -> PResult<'_, RDBVersion> {
+    FILE_RANGE.with(|r| r.set((input.as_ptr() as usize, input.len())));
+    context("unsupported RDB version", verify(rdb_header, |v: &RDBVersion| v.supported().is_ok()))(input)
+}
+
+// FA {string} {string}
+pub fn aux_field(input: &[u8]) -> PResult<'_, AuxField<'_>> {
+    let (rest, _) = trace_input(input, "aux field")?;
+    map(preceded(tag(&[0xfa][..]), pair(encoded_string, encoded_string)), |(k, v)| AuxField(k, v))(rest)
+}
+
+// F5 {library code}, a library of Redis functions
+pub fn function(input: &[u8]) -> PResult<'_, EncodedString<'_>> {
+    let (rest, _) = trace_input(input, "function")?;
+    preceded(tag(&[0xf5][..]), encoded_string)(rest)
+}
+
+pub fn database(input: &[u8]) -> PResult<'_, Database<'_>> {
+    map(tuple((database_number, opt(resize_db), many0(record))), |(n, _, r)| Database(n, r))(input)
+}
+
+/// any element of the file but the header, a record or the end: a database number, RESIZEDB,
+/// an aux field, module aux data or a function library
+pub fn opcode(input: &[u8]) -> PResult<'_, ()> {
+    alt((map(database_number, |_| ()), resize_db, map(aux_field, |_| ()), map(module_aux, |_| ()), map(function, |_| ())))(input)
+}
+
+// aux fields, in files of a version which has them
+fn aux_fields<'a>(v: &RDBVersion, input: &'a [u8]) -> PResult<'a, Vec<AuxField<'a>>> {
+    if v.since(AUX_RDB_VERSION) {
+        many0(aux_field)(input)
+    } else {
+        Ok((input, Vec::new()))
+    }
+}
+
+/// skip the global data of modules, which has no place in `RDB`, in files of a version which has it
+fn skip_module_aux<'a>(v: &RDBVersion, input: &'a [u8]) -> PResult<'a, ()> {
+    if !v.since(MODULE_AUX_RDB_VERSION) {
+        return Ok((input, ()));
+    }
+    let (rest, ids) = many0(module_aux)(input)?;
+    for id in ids {
+        warn!("skip aux data of module {}", module_name(id));
+    }
+    Ok((rest, ()))
+}
+
+/// skip the function libraries, which have no place in `RDB`, in files of a version which has them
+fn skip_functions<'a>(v: &RDBVersion, input: &'a [u8]) -> PResult<'a, ()> {
+    if !v.since(FUNCTION_RDB_VERSION) {
+        return Ok((input, ()));
+    }
+    let (rest, libraries) = many0(function)(input)?;
+    if !libraries.is_empty() {
+        warn!("skip function libraries: {}", libraries.len());
+    }
+    Ok((rest, ()))
+}
+
+pub fn rdb(input: &[u8]) -> PResult<'_, RDB<'_>> {
+    let (rest, v) = rdb_version(input)?;
+    let (rest, (a, _, _, d, _)) = tuple((
+        |i| aux_fields(&v, i),
+        |i| skip_module_aux(&v, i),
+        |i| skip_functions(&v, i),
+        many0(database),
+        |i| skip_module_aux(&v, i),
+    ))(rest)?;
+    let (rest, c) = rdb_end(rest)?;
+    Ok((rest, RDB(v, a, d, c)))
+}
+
+// FF {checksum} and the end of input
+fn rdb_end(input: &[u8]) -> PResult<'_, Option<Checksum<'_>>> {
+    let (rest, _) = trace_input(input, "end of rdb")?;
+    let (rest, c) = preceded(end_of_rdb, opt(checksum))(rest)?;
+    let (rest, _) = eof(rest)?;
+    Ok((rest, c))
+}
+
+/// record of an unsupported value type, skipped by `rdb_skipping`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct SkippedRecord {
+    pub db:         u32,
+    /// the key, if it could be parsed
+    pub key:        Option<Vec<u8>>,
+    pub value_type: u8,
+    /// offset of the record in the file
+    pub offset:     usize,
+}
+
+impl fmt::Display for SkippedRecord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "skip record of unsupported value type 0x{:02x} at offset {} in database {}", self.value_type, self.offset, self.db)?;
+        if let Some(ref key) = self.key {
+            write!(f, ", key {}", String::from_utf8_lossy(key))?;
+        }
+        Ok(())
+    }
+}
+
+// whether `input` starts a database, the end of the file or a record, of any value type
+fn at_boundary(input: &[u8]) -> bool {
+    if let Some(&0xfe) | Some(&0xff) = input.first() {
+        return true;
+    }
+    match opt(alt((expiry_time_msec, expiry_time_sec)))(input) {
+        Ok((after, _)) => after.first().is_some_and(|&t| t & 0x80 == 0) && encoded_string(&after[1..]).is_ok(),
+        Err(_)         => false,
+    }
+}
+
+// rest after a value of an unsupported type, by the structures of the newer types: a string,
+// as the listpacks, a length and as many strings, as the quicklist, or a length and as many
+// containers and strings, as the quicklist 2; `None` unless one is followed by a boundary
+fn skip_value(input: &[u8]) -> Option<&[u8]> {
+    let nodes = |i| {
+        let (rest, n) = encoded_length(i)?;
+        count(pair(encoded_length, encoded_string), u32::from(n) as usize)(rest)
+    };
+    [
+        encoded_string(input).map(|(r, _)| r),
+        encoded_sequence(input).map(|(r, _)| r),
+        nodes(input).map(|(r, _)| r),
+    ].into_iter().filter_map(|r| r.ok()).find(|r| at_boundary(r))
+}
+
+// records of database `db` up to the next database number or end, a record of an unsupported
+// value type skipped by `skip_value`, or else up to the next 0xFE or 0xFF byte; `file` is the
+// whole input, for offsets
+fn records_skipping<'a>(file: &[u8], input: &'a [u8], db: u32, skipped: &mut Vec<SkippedRecord>) -> PResult<'a, Vec<Record<'a>>> {
+    let mut records = Vec::new();
+    let mut rest = input;
+    loop {
+        match record(rest) {
+            Ok((r, record))    => { records.push(record); rest = r; continue; },
+            Err(Err::Error(_)) => (),
+            Err(e)             => return Err(e),
+        }
+        let (after, _) = opt(alt((expiry_time_msec, expiry_time_sec)))(rest)?;
+        let t = match after.first() {
+            Some(&t) if t & 0x80 == 0 && ValueType::try_from(t).is_err() => t,
+            // end of the database, or an error in a record of a supported type
+            _ => return Ok((rest, records)),
+        };
+        let key = encoded_string(&after[1..]).ok();
+        let value_end = key.as_ref().and_then(|&(r, _)| skip_value(r));
+        let key = key.and_then(|(_, k)| RDBDec::decode(&k).ok());
+        skipped.push(SkippedRecord { db, key, value_type: t, offset: file.len() - rest.len() });
+        rest = match value_end {
+            Some(r) => r,
+            None    => match rest.iter().skip(1).position(|&b| b == 0xfe || b == 0xff) {
+                Some(n) => &rest[n + 1..],
+                None    => return Err(Err::Incomplete(Needed::Unknown)),
+            },
+        };
+    }
+}
+
+/// `rdb`, but a record of an unsupported value type, as of a newer RDB version, is added to
+/// `skipped` and skipped instead of failing: by the structure of its value if it is one of the
+/// newer types, or else up to the next byte which may start a database or the end of the file
+pub fn rdb_skipping<'a>(input: &'a [u8], skipped: &mut Vec<SkippedRecord>) -> PResult<'a, RDB<'a>> {
+    let (rest, v) = rdb_version(input)?;
+    let (mut rest, (a, _, _)) = tuple((|i| aux_fields(&v, i), |i| skip_module_aux(&v, i), |i| skip_functions(&v, i)))(rest)?;
+    let mut dbs = Vec::new();
+    loop {
+        let (r, n) = match database_number(rest) {
+            Ok(r)              => r,
+            Err(Err::Error(_)) => break,
+            Err(e)             => return Err(e),
+        };
+        let (r, _) = opt(resize_db)(r)?;
+        let (r, records) = records_skipping(input, r, n.1, skipped)?;
+        dbs.push(Database(n, records));
+        rest = r;
+    }
+    let (rest, _) = skip_module_aux(&v, rest)?;
+    let (rest, c) = rdb_end(rest)?;
+    Ok((rest, RDB(v, a, dbs, c)))
+}
 
 /// test
+// RDB files of the tests of the bindings: a => 1 and l => [x] in database 0, b => 2 expiring at
+// 1000 ms in database 2, as A.rdb of tests/cli.rs, and a => 3 and c => 4 in database 0, as B.rdb
 #[cfg(test)]
-use nom::IResult::*;
+pub const TEST_RDB_A: &[u8] = b"REDIS0006\xfe\x00\x00\x01a\x011\x01\x01l\x01\x01x\xfe\x02\xfc\xe8\x03\x00\x00\x00\x00\x00\x00\x00\x01b\x012\xff\x00\x00\x00\x00\x00\x00\x00\x00";
+#[cfg(test)]
+pub const TEST_RDB_B: &[u8] = b"REDIS0006\xfe\x00\x00\x01a\x013\x00\x01c\x014\xff\x00\x00\x00\x00\x00\x00\x00\x00";
 
 #[test]
 fn encoded_length_test() {
     let case_00_1_in = [0b00000000];
-    assert_eq!(encoded_length(&case_00_1_in), Done(&[][..], I(0, &case_00_1_in[..])));
+    assert_eq!(encoded_length(&case_00_1_in), Ok((&[][..], I(0, &case_00_1_in[..]))));
 
     let case_00_2_in = [0b00111111];
-    assert_eq!(encoded_length(&case_00_2_in), Done(&[][..], I(63, &case_00_2_in[..])));
+    assert_eq!(encoded_length(&case_00_2_in), Ok((&[][..], I(63, &case_00_2_in[..]))));
 
 
     let case_01_1_in = [0b01000000, 0x40];
-    assert_eq!(encoded_length(&case_01_1_in), Done(&[][..], I(64, &case_01_1_in[..])));
+    assert_eq!(encoded_length(&case_01_1_in), Ok((&[][..], I(64, &case_01_1_in[..]))));
 
     let case_01_2_in = [0b01111111, 0xff];
-    assert_eq!(encoded_length(&case_01_2_in), Done(&[][..], I(16383, &case_01_2_in[..])));
+    assert_eq!(encoded_length(&case_01_2_in), Ok((&[][..], I(16383, &case_01_2_in[..]))));
 
 
     let case_10_1_in = [0b10000000, 0x00, 0x00, 0x40, 0x00];
-    assert_eq!(encoded_length(&case_10_1_in), Done(&[][..], I(16384, &case_10_1_in[..])));
+    assert_eq!(encoded_length(&case_10_1_in), Ok((&[][..], I(16384, &case_10_1_in[..]))));
 
     let case_10_2_in = [0b10000000, 0xff, 0xff, 0xff, 0xff];
-    assert_eq!(encoded_length(&case_10_2_in), Done(&[][..], I(4294967295, &case_10_2_in[..])));
+    assert_eq!(encoded_length(&case_10_2_in), Ok((&[][..], I(4294967295, &case_10_2_in[..]))));
+
+
+    let case_10_3_in = [0x81, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x40, 0x00];
+    assert_eq!(encoded_length(&case_10_3_in), Ok((&[][..], I(16384, &case_10_3_in[..]))));
+
+    let case_10_4_in = [0x81, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00];
+    assert!(encoded_length(&case_10_4_in).is_err());
+
+    for b in [0x82, 0x9f, 0xbf].iter() {
+        let case_10_5_in = [*b, 0x00, 0x00, 0x40, 0x00, 0x00, 0x00, 0x00, 0x00];
+        assert!(encoded_length(&case_10_5_in).is_err());
+    }
 
 
     let case_11_1_in = [0b11000000];
-    assert_eq!(encoded_length(&case_11_1_in), Done(&[][..], S(0, &case_11_1_in[..])));
+    assert_eq!(encoded_length(&case_11_1_in), Ok((&[][..], S(0, &case_11_1_in[..]))));
 
     let case_11_2_in = [0b11000011];
-    assert_eq!(encoded_length(&case_11_2_in), Done(&[][..], S(3, &case_11_2_in[..])));
+    assert_eq!(encoded_length(&case_11_2_in), Ok((&[][..], S(3, &case_11_2_in[..]))));
+}
+
+#[test]
+fn encode_length_test() {
+    for n in [0, 63, 64, 16383, 16384, 4294967295].iter() {
+        let bytes = encode_length(*n);
+        assert_eq!(encoded_length(&bytes[..]), Ok((&[][..], I(*n, &bytes[..]))));
+    }
+}
+
+#[test]
+fn value_type_test() {
+    for t in [0x00, 0x01, 0x02, 0x03, 0x04, 0x0a, 0x0b, 0x0c, 0x0d].iter() {
+        assert_eq!(ValueType::try_from(*t).map(|t| t as u8), Ok(*t));
+    }
+    assert_eq!(ValueType::try_from(0x05), Err(UnknownValueType(0x05)));
+    assert_eq!(ValueType::try_from(0x0f), Err(UnknownValueType(0x0f)));
+    assert_eq!(value_type(&[0x0b]), Ok((&[][..], ValueType::Intset)));
+    assert!(value_type(&[0x10]).is_err());
+    assert_eq!(ValueType::SortedsetZiplist.to_string(), "sortedset-ziplist");
 }
 
 #[test]
 fn encoded_string_test() {
     let case_raw_1_in = [0b00000001, 0x30];
     let case_raw_1_result = Raw(I(1, &case_raw_1_in[0..1]), b"0");
-    assert_eq!(encoded_string(&case_raw_1_in), Done(&[][..], case_raw_1_result));
+    assert_eq!(encoded_string(&case_raw_1_in), Ok((&[][..], case_raw_1_result)));
 
 
     let case_int_1_in = [0b11000000, 0x30, 0x00, 0x00, 0x00];
     let case_int_1_result = Int(S(0, &case_int_1_in[0..1]), &case_int_1_in[1..2]);
     let case_int_1_rest = [0x00, 0x00, 0x00];
-    assert_eq!(encoded_string(&case_int_1_in), Done(&case_int_1_rest[..], case_int_1_result));
+    assert_eq!(encoded_string(&case_int_1_in), Ok((&case_int_1_rest[..], case_int_1_result)));
 
     let case_int_2_in = [0b11000001, 0x30, 0x00, 0x00, 0x00];
     let case_int_2_result = Int(S(1, &case_int_2_in[0..1]), &case_int_2_in[1..3]);
     let case_int_2_rest = [0x00, 0x00];
-    assert_eq!(encoded_string(&case_int_2_in), Done(&case_int_2_rest[..], case_int_2_result));
+    assert_eq!(encoded_string(&case_int_2_in), Ok((&case_int_2_rest[..], case_int_2_result)));
 
     let case_int_3_in = [0b11000010, 0x30, 0x00, 0x00, 0x00];
     let case_int_3_result = Int(S(2, &case_int_3_in[0..1]), &case_int_3_in[1..]);
     let case_int_3_rest = [];
-    assert_eq!(encoded_string(&case_int_3_in), Done(&case_int_3_rest[..], case_int_3_result));
+    assert_eq!(encoded_string(&case_int_3_in), Ok((&case_int_3_rest[..], case_int_3_result)));
 
 
     let case_lzf_1_in = [0b11000011, 0b00000001, 0b00000001, 0x30];
@@ -673,7 +1715,7 @@ fn encoded_string_test() {
                                 I(1, &case_lzf_1_in[2..3]),
                                 &case_lzf_1_in[3..]);
     let case_lzf_1_rest = [];
-    assert_eq!(encoded_string(&case_lzf_1_in), Done(&case_lzf_1_rest[..], case_lzf_1_result));
+    assert_eq!(encoded_string(&case_lzf_1_in), Ok((&case_lzf_1_rest[..], case_lzf_1_result)));
 }
 
 #[test]
@@ -689,14 +1731,180 @@ fn decode_encoded_string_test() {
         0x01, 0x61, 0x61, // literal aa
     ];
     match encoded_string(&case_1[..]) {
-        Done(_, e) => {
+        Ok((_, e)) => {
             match String::decode(&e) {
                 Ok(s) => assert_eq!(s, "aaaaaaaaaaaaaaaa1aaaaaaaaaaaaaaaa".to_string()),
-                _     => assert!(false),
+                _     => panic!(),
             }
         },
-        _ => assert!(false),
+        _ => panic!(),
     }
+
+    let case_2 = [
+        0xc3, 0x06, 0x06,             // EncodedLength
+        0x02, 0x61, 0x62, 0x63,       // literal abc
+        0x20, 0x02,                   // short backref
+    ];
+    match encoded_string(&case_2[..]) {
+        Ok((_, e)) => assert_eq!(String::decode(&e).unwrap(), "abcabc"),
+        _ => panic!(),
+    }
+
+    let case_3 = [0xc3, 0x02, 0x06, 0x20, 0x02]; // backref before any output
+    match encoded_string(&case_3[..]) {
+        Ok((_, e)) => match String::decode(&e) {
+            Err(RmergerError::Lzf) => (),
+            _ => panic!(),
+        },
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn expiry_time_as_millis_test() {
+    let case_msec_1_in = [0xfc, 0xe8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00];
+    match expiry_time_msec(&case_msec_1_in) {
+        Ok((_, e)) => assert_eq!(e.as_millis(), 1000),
+        _ => panic!(),
+    }
+
+    // 9999-12-31T23:59:59.999Z
+    let case_msec_2_in = [0xfc, 0xff, 0xdb, 0x1f, 0xd2, 0x77, 0xe6, 0x00, 0x00];
+    match expiry_time_msec(&case_msec_2_in) {
+        Ok((_, e)) => assert_eq!(e.as_millis(), 253402300799999),
+        _ => panic!(),
+    }
+
+    let case_sec_1_in = [0xfd, 0x80, 0x5f, 0x53, 0x5a];
+    match expiry_time_sec(&case_sec_1_in) {
+        Ok((_, e)) => assert_eq!(e.as_millis(), 1515413376000),
+        _ => panic!(),
+    }
+}
+
+#[test]
+fn decode_int_encoded_string_test() {
+    let cases: [(&[u8], &str); 4] = [
+        (&[0xc0, 0x7b],                   "123"),
+        (&[0xc0, 0xff],                   "-1"),
+        (&[0xc1, 0x39, 0x30],             "12345"),
+        (&[0xc2, 0x00, 0xca, 0x9a, 0x3b], "1000000000"),
+    ];
+    for &(bytes, expected) in cases.iter() {
+        match encoded_string(bytes) {
+            Ok((_, e)) => assert_eq!(String::decode(&e).unwrap(), expected),
+            _ => panic!(),
+        }
+    }
+}
+
+#[test]
+fn decode_intset_test() {
+    fn decode(b: &[u8]) -> Result<Vec<i64>> {
+        Vec::<i64>::decode(&EncodedIntset(Raw(I(b.len() as u32, &[]), b)))
+    }
+    let case_2 = [0x02, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0xff, 0xff, 0x39, 0x30];
+    assert_eq!(decode(&case_2).unwrap(), vec![-1, 12345]);
+
+    let case_8 = [0x08, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x80];
+    assert_eq!(decode(&case_8).unwrap(), vec![i64::MIN]);
+
+    // truncated header, element size 3, and fewer bytes than elements
+    assert!(decode(&case_2[..6]).is_err());
+    assert!(decode(&[0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]).is_err());
+    assert!(decode(&case_2[..11]).is_err());
+}
+
+#[test]
+fn decode_ziplist_test() {
+    // "ab", 12, -2 and 300: a string, an integer in the encoding, an 8-bit and a 16-bit integer
+    let case = [
+        0x18, 0x00, 0x00, 0x00, 0x13, 0x00, 0x00, 0x00, 0x04, 0x00,
+        0x00, 0x02, 0x61, 0x62,
+        0x04, 0xfd,
+        0x02, 0xfe, 0xfe,
+        0x03, 0xc0, 0x2c, 0x01,
+        0xff,
+    ];
+    let s = Raw(I(case.len() as u32, &[]), &case[..]);
+    let entries: Vec<Vec<u8>> = RDBDec::decode(&EncodedZiplist(s)).unwrap();
+    assert_eq!(entries, vec![b"ab".to_vec(), b"12".to_vec(), b"-2".to_vec(), b"300".to_vec()]);
+    let pairs: Vec<(Vec<u8>, Vec<u8>)> = RDBDec::decode(&EncodedHashmapZiplist(s)).unwrap();
+    assert_eq!(pairs, vec![(b"ab".to_vec(), b"12".to_vec()), (b"-2".to_vec(), b"300".to_vec())]);
+
+    // odd number of pairs, truncated entry, and wrong number of entries
+    let mut odd = case;
+    odd[8] = 0x03;
+    odd[19] = 0xff;
+    let odd = &odd[..20];
+    assert!(Vec::<(Vec<u8>, Vec<u8>)>::decode(&EncodedSortedsetZiplist(Raw(I(20, &[]), odd))).is_err());
+    assert!(Vec::<Vec<u8>>::decode(&EncodedZiplist(Raw(I(21, &[]), &case[..21]))).is_err());
+    let mut wrong = case;
+    wrong[8] = 0x05;
+    assert!(Vec::<Vec<u8>>::decode(&EncodedZiplist(Raw(I(24, &[]), &wrong[..]))).is_err());
+}
+
+#[test]
+fn ziplist_encode_test() {
+    let entries = |zl: &[u8]| Vec::<Vec<u8>>::decode(&EncodedZiplist(Raw(I(zl.len() as u32, &[]), zl))).unwrap();
+    let case: Vec<&[u8]> = vec![b"ab", b"12", b"-2", b"300"];
+    assert_eq!(ziplist_encode(&case), [
+        0x18, 0x00, 0x00, 0x00, 0x13, 0x00, 0x00, 0x00, 0x04, 0x00,
+        0x00, 0x02, 0x61, 0x62, 0x04, 0xfd, 0x02, 0xfe, 0xfe, 0x03, 0xc0, 0x2c, 0x01, 0xff,
+    ]);
+    assert_eq!(ziplist_encode(&[]), [0x0b, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff]);
+
+    // every integer width, strings which are not canonical integers, and each string length encoding,
+    // the 300-byte one making the next entry store its previous length in 5 bytes
+    let long = vec![b'x'; 300];
+    let longer = vec![b'y'; 0x4000];
+    let case: Vec<&[u8]> = vec![
+        b"0", b"-1", b"127", b"-32768", b"8388607", b"-2147483648", b"9223372036854775807",
+        b"007", b"+1", b"1.5", b"", &long, b"z", &longer,
+    ];
+    let zl = ziplist_encode(&case);
+    assert_eq!(entries(&zl), case.iter().map(|s| s.to_vec()).collect::<Vec<_>>());
+    assert_eq!(u32::from_le_bytes([zl[0], zl[1], zl[2], zl[3]]) as usize, zl.len());
+    // the tail entry: the 7 bytes of "z" with its 5-byte previous length, and a 4-byte string length
+    let tail = u32::from_le_bytes([zl[4], zl[5], zl[6], zl[7]]) as usize;
+    assert_eq!(&zl[tail..tail + 6], &[0x07, 0x80, 0x00, 0x00, 0x40, 0x00]);
+}
+
+#[test]
+fn module_test() {
+    // ReJSON-RL, encoding version 3
+    let id = b"ReJSON-RL".iter()
+        .fold(0, |a, c| a << 6 | MODULE_NAME_CHARSET.iter().position(|x| x == c).unwrap() as u64) << 10 | 3;
+    let mut value = vec![0x81];
+    value.extend((0..8).rev().map(|i| (id >> (8 * i)) as u8));
+    value.extend(&[0x02, 0x05, 0x05, 0x01, 0x78, 0x04, 0, 0, 0, 0, 0, 0, 0xf0, 0x3f, 0x00]); // 5, "x", 1.0, EOF
+
+    let mut case = vec![0x07, 0x01, 0x6d];
+    case.extend(&value);
+    let (rest, r) = record(&case).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(r.1, V7(EncodedModule(id, &value[..])));
+    assert_eq!((EncodedModule(id, &value[..]).name(), EncodedModule(id, &value[..]).encoding_version()), ("ReJSON-RL".to_string(), 3));
+    let mut bytes = Vec::new();
+    r.ser(&mut bytes).unwrap();
+    assert_eq!(bytes, case);
+
+    // module aux data before the databases is skipped, an unknown opcode fails
+    let mut file = b"REDIS0009\xf7".to_vec();
+    file.extend(&value[..9]);
+    file.extend(&[0x02, 0x02, 0x02, 0x07, 0x00, 0xfe, 0x00]);
+    file.extend(&case);
+    file.extend(&[0xff, 0, 0, 0, 0, 0, 0, 0, 0]);
+    let rdb = parse_rdb(&file).unwrap();
+    assert_eq!((rdb.1.len(), rdb.2[0].1.len()), (0, 1));
+    let mut invalid = case.clone();
+    invalid[14] = 0x06;
+    assert!(record(&invalid).is_err());
+    // the module type before RDB v8 has no EOF opcode to find the end of its value by
+    let mut legacy = case.clone();
+    legacy[0] = 0x06;
+    assert!(record(&legacy).is_err());
+    assert_eq!(ValueType::try_from(0x06), Err(UnknownValueType(0x06)));
 }
 
 #[test]
@@ -704,15 +1912,15 @@ fn rdb_serde_test() {
     let case_1 = [
         0x52, 0x45, 0x44, 0x49, 0x53, 0x30, 0x30, 0x30, 0x36, // REDIS0004
         0xfe, 0x00,                                           // <DatabaseNumber 0>
-        VT_LIST.bits(),
+        ValueType::List as u8,
         0x01, 0x30,
         0x02, 0x01, 0x31, 0x01, 0x32,
         0xfc, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff,
-        VT_SET.bits(),
+        ValueType::Set as u8,
         0x01, 0x31,
         0x02, 0x01, 0x31, 0x01, 0x32,
         0xfd, 0x00, 0x00, 0x00, 0xff,
-        VT_SORTEDSET.bits(),
+        ValueType::Sortedset as u8,
         0x01, 0x31,
         0x02,
         0x01, 0x31, 0x04, 0x00, 0x00, 0x00, 0x00,
@@ -722,10 +1930,267 @@ fn rdb_serde_test() {
     ];
     let mut case_1_ser = Vec::new();
     match rdb(&case_1[..]) {
-        Done(_, rdb) => {
+        Ok((_, rdb)) => {
             assert!(rdb.ser(&mut case_1_ser).is_ok());
             assert_eq!(&case_1[..], &case_1_ser[..]);
         },
-        _ => assert!(false),
+        _ => panic!(),
+    }
+
+    let case_2 = [
+        0x52, 0x45, 0x44, 0x49, 0x53, 0x30, 0x30, 0x30, 0x37, // REDIS0007
+        0xfa, 0x09, 0x72, 0x65, 0x64, 0x69, 0x73, 0x2d, 0x76, 0x65, 0x72,
+        0x05, 0x33, 0x2e, 0x32, 0x2e, 0x30,                   // redis-ver => 3.2.0
+        0xfa, 0x0a, 0x72, 0x65, 0x64, 0x69, 0x73, 0x2d, 0x62, 0x69, 0x74, 0x73,
+        0xc0, 0x40,                                           // redis-bits => 64
+        0xfe, 0x00,                                           // <DatabaseNumber 0>
+        0x00, 0x01, 0x30, 0x01, 0x31,                         // 0 => 1
+        0xff,                                                 // end of rdb
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00        // checksum
+    ];
+    let mut case_2_ser = Vec::new();
+    match rdb(&case_2[..]) {
+        Ok((_, rdb)) => {
+            assert_eq!(rdb.1.len(), 2);
+            assert_eq!(String::decode(&rdb.1[0].1).unwrap(), "3.2.0");
+            assert_eq!(String::decode(&rdb.1[1].1).unwrap(), "64");
+            assert!(rdb.ser(&mut case_2_ser).is_ok());
+            assert_eq!(&case_2[..], &case_2_ser[..]);
+        },
+        _ => panic!(),
+    }
+
+    // the nom 7 parser reads both cases back bit-identically, strict or skipping
+    for case in &[&case_1[..], &case_2[..]] {
+        for r in [parse_rdb(case).unwrap(), parse_rdb_skipping(case).unwrap()] {
+            let mut bytes = Vec::new();
+            assert_eq!(r.ser(&mut bytes).unwrap(), case.len());
+            assert_eq!(&bytes[..], *case);
+        }
+    }
+}
+
+#[test]
+fn owned_record_test() {
+    let case = [
+        0xfc, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0xff,
+        ValueType::List as u8,
+        0x01, 0x30,
+        0x02, 0x01, 0x31, 0x01, 0x32,
+    ];
+    let (owned, value) = match record(&case.to_vec()[..]) {
+        Ok((_, r)) => (r.to_owned(), r.1.to_owned()),
+        _          => unreachable!(),
+    };
+    // the owned types outlive the parsed buffer and move across threads
+    let (owned, value) = ::std::thread::spawn(move || (owned, value)).join().unwrap();
+
+    let mut owned_ser = Vec::new();
+    assert!(owned.ser(&mut owned_ser).is_ok());
+    assert_eq!(&owned_ser[..], &case[..]);
+    assert_eq!(owned.record().1, value.value());
+    assert_eq!(value.value_type(), ValueType::List);
+
+    let mut value_ser = Vec::new();
+    assert!(value.ser(&mut value_ser).is_ok());
+    assert_eq!(&value_ser[..], &case[12..]);
+}
+
+#[test]
+fn rdb_accessors_test() {
+    let case = [
+        0x52, 0x45, 0x44, 0x49, 0x53, 0x30, 0x30, 0x30, 0x36, // REDIS0006
+        0xfe, 0x00,                                           // <DatabaseNumber 0>
+        0x00, 0x01, 0x30, 0x01, 0x31,                         // 0 => 1
+        0x00, 0x01, 0x31, 0x01, 0x32,                         // 1 => 2
+        0xfe, 0x02,                                           // <DatabaseNumber 2>
+        0x00, 0x01, 0x32, 0x01, 0x33,                         // 2 => 3
+        0xff,                                                 // end of rdb
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00        // checksum
+    ];
+    let r = parse_done(rdb(&case[..])).unwrap();
+    assert_eq!(r.databases().map(|db| db.number()).collect::<Vec<u32>>(), vec![0, 2]);
+    assert_eq!(r.databases().map(|db| db.records().count()).collect::<Vec<usize>>(), vec![2, 1]);
+    let keys: Vec<String> = r.databases().flat_map(|db| db.records()).map(|r| String::decode(&r.0).unwrap()).collect();
+    assert_eq!(keys, vec!["0", "1", "2"]);
+
+    let records: Vec<(u32, String)> = (&r).into_iter().map(|(num, r)| (num.1, String::decode(&r.0).unwrap())).collect();
+    assert_eq!(records, vec![(0, "0".to_string()), (0, "1".to_string()), (2, "2".to_string())]);
+    assert_eq!(r.iter_db(2).map(|(num, _)| num.1).collect::<Vec<u32>>(), vec![2]);
+    assert_eq!(r.iter_db(1).count(), 0);
+    assert_eq!((&r).into_iter().filter_type(ValueType::String).count(), 3);
+    assert_eq!(r.iter_db(0).filter_type(ValueType::List).count(), 0);
+}
+
+#[test]
+fn rdb_version_order_test() {
+    let v = |n| RDBVersion::try_from(n).unwrap();
+    assert_eq!(v(6), RDBVersion(*b"0006"));
+    assert_eq!(v(10).number().unwrap(), 10);
+    assert!(RDBVersion::try_from(10000).is_err());
+    assert!(v(9) > v(7));
+    assert!(RDBVersion(*b"00x6") < v(1));
+    assert_eq!([v(6), v(11), v(7)].iter().max(), Some(&v(11)));
+}
+
+#[test]
+fn rdb_version_test() {
+    let body = b"\xfe\x00\x00\x01k\x01v\xff\x00\x00\x00\x00\x00\x00\x00\x00";
+    let file = |header: &[u8], rest: &[u8]| [header, rest, &body[..]].concat();
+
+    assert_eq!(RDBVersion(*b"0012").supported().unwrap(), 12);
+    assert!(parse_rdb(&file(b"REDIS0012", b"")).is_ok());
+    match parse_rdb(&file(b"REDIS0013", b"")) {
+        Err(RmergerError::UnsupportedVersion(13)) => (),
+        r => panic!("{:?}", r.map(|r| r.0)),
+    }
+    assert!(parse_rdb_skipping(&file(b"REDIS0000", b"")).is_err());
+    assert!(parse_rdb(&file(b"REDIS00x6", b"")).is_err());
+    assert!(rdb(&file(b"REDIS0013", b"")).is_err());
+
+    // aux fields since v7, module aux data since v9
+    let aux = b"\xfa\x01a\x01b";
+    assert_eq!(parse_rdb(&file(b"REDIS0007", aux)).unwrap().1.len(), 1);
+    assert!(parse_rdb(&file(b"REDIS0006", aux)).is_err());
+    assert!(parse_rdb(&file(b"REDIS0008", b"\xf7\x00\x02\x00")).is_err());
+}
+
+#[test]
+fn parse_rdb_error_offset_test() {
+    let header = [0x52, 0x45, 0x44, 0x49, 0x53, 0x30, 0x30, 0x30, 0x36]; // REDIS0006
+    // <DatabaseNumber 0> 0 => 1, then a record of the unknown type 0x0e
+    let corrupt = [&header[..], &[0xfe, 0x00, 0x00, 0x01, 0x30, 0x01, 0x31, 0x0e, 0x01, 0x31, 0xff][..]].concat();
+    match parse_rdb(&corrupt) {
+        Err(RmergerError::ParseErrorAt { offset, ref dump, .. }) => {
+            assert_eq!(offset, 16);
+            assert_eq!(dump, "36 fe 00 00 01 30 01 31 [0e] 01 31 ff");
+        },
+        r => panic!("unexpected {:?}", r),
+    }
+    // truncated in the middle of the record 0 => 1
+    match parse_rdb(&corrupt[..14]) {
+        Err(RmergerError::ParseErrorAt { offset, ref dump, ref error }) => {
+            assert_eq!(offset, 11);
+            assert!(dump.ends_with("[00] 01 30"));
+            assert!(matches!(**error, RmergerError::ParseIncomplete));
+        },
+        r => panic!("unexpected {:?}", r),
+    }
+    assert_eq!(error_offset(b"RDB"), 0);
+    assert_eq!(hex_dump(&header, 9), "45 44 49 53 30 30 30 36 [EOF]");
+}
+
+#[test]
+fn rdb_roundtrip_test() {
+    let corpus = [
+        &b"REDIS0007"[..],
+        &[0xfa, 0x03, 0x76, 0x65, 0x72, 0x01, 0x37],                                  // aux ver => 7
+        &[0xfe, 0x00],                                                                // <DatabaseNumber 0>
+        &[0xfc, 0xe8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x61, 0xc0, 0x05], // a => 5, expiring in ms
+        &[0xfd, 0x01, 0x00, 0x00, 0x00, 0x01, 0x01, 0x6c, 0x02, 0x01, 0x78, 0xc1, 0x00, 0x01], // l => [x, 256], in s
+        &[0x02, 0x01, 0x73, 0x01, 0x01, 0x6d],                                        // s => {m}
+        &[0x03, 0x01, 0x7a, 0x01, 0x01, 0x6d, 0x03, 0x31, 0x2e, 0x35],                // z => {m: 1.5}
+        &[0x04, 0x01, 0x68, 0x01, 0x01, 0x66, 0x01, 0x76],                            // h => {f: v}
+        &[0x0a, 0x01, 0x71, 0x03, 0x61, 0x62, 0x63],                                  // q => <ziplist>
+        &[0x0b, 0x01, 0x69, 0x02, 0x78, 0x79],                                        // i => <intset>
+        &[0x00, 0x01, 0x63, 0xc3, 0x02, 0x03, 0x00, 0x61],                            // c => <lzf>
+        &[0xfe, 0x40, 0x40],                                                          // <DatabaseNumber 64>
+        &[0x00, 0x01, 0x6b, 0x01, 0x76],                                              // k => v
+        &[0xff, 0x01, 0x02, 0x03, 0x04, 0x05, 0x06, 0x07, 0x08],                      // end of rdb, checksum
+    ].concat();
+
+    let r = parse_rdb(&corpus).unwrap();
+    assert_eq!((r.1.len(), r.databases().map(|db| db.records().count()).collect::<Vec<usize>>()), (1, vec![8, 1]));
+    let mut bytes = Vec::new();
+    assert_eq!(r.ser(&mut bytes).unwrap(), corpus.len());
+    assert_eq!(bytes, corpus);
+}
+
+#[test]
+fn rdb_skipping_test() {
+    let rdb_file = [
+        &b"REDIS0009"[..],
+        &[0xfe, 0x00],                                           // <DatabaseNumber 0>
+        &[0x00, 0x01, 0x61, 0x01, 0x31],                         // a => 1
+        &[0x0f, 0x01, 0x78, 0x03, 0x01, 0x02, 0x03],             // x => <stream>
+        &[0xfe, 0x01],                                           // <DatabaseNumber 1>
+        &[0xfc, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x10, 0x01, 0x79, 0x00], // y => <0x10>, expiring
+        &[0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // end of rdb, checksum
+    ].concat();
+    assert!(parse_rdb(&rdb_file).is_err());
+
+    let mut skipped = Vec::new();
+    let r = parse_done(rdb_skipping(&rdb_file, &mut skipped)).unwrap();
+    assert_eq!(r.databases().map(|db| (db.number(), db.records().count())).collect::<Vec<(u32, usize)>>(), vec![(0, 1), (1, 0)]);
+    assert_eq!(skipped, vec![
+        SkippedRecord { db: 0, key: Some(b"x".to_vec()), value_type: 0x0f, offset: 16 },
+        SkippedRecord { db: 1, key: Some(b"y".to_vec()), value_type: 0x10, offset: 25 },
+    ]);
+    assert_eq!(skipped[0].to_string(), "skip record of unsupported value type 0x0f at offset 16 in database 0, key x");
+    assert!(parse_rdb_skipping(&rdb_file).is_ok());
+}
+
+#[test]
+fn rdb_skipping_listpack_test() {
+    let listpack = [
+        0x0d, 0x00, 0x00, 0x00, 0x02, 0x00, // total bytes, elements
+        0x81, 0x66, 0x02, 0x81, 0x76, 0x02, // f, v
+        0xff,                               // end of listpack
+    ];
+    let rdb_file = [
+        &b"REDIS0011"[..],
+        &[0xfe, 0x00],                                           // <DatabaseNumber 0>
+        &[0x00, 0x01, 0x61, 0x01, 0x31],                         // a => 1
+        &[0x10, 0x01, 0x68, listpack.len() as u8],               // h => <hash listpack>
+        &listpack,
+        &[0x00, 0x01, 0x62, 0x01, 0x32],                         // b => 2
+        &[0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // end of rdb, checksum
+    ].concat();
+
+    // the 0xFF ending the listpack is not taken for the end of the file
+    let mut skipped = Vec::new();
+    let r = parse_done(rdb_skipping(&rdb_file, &mut skipped)).unwrap();
+    let keys: Vec<Vec<u8>> = r.databases().flat_map(|db| db.records().map(|r| Vec::<u8>::decode(&r.0).unwrap())).collect();
+    assert_eq!(keys, vec![b"a".to_vec(), b"b".to_vec()]);
+    assert_eq!(skipped, vec![SkippedRecord { db: 0, key: Some(b"h".to_vec()), value_type: 0x10, offset: 16 }]);
+}
+
+#[test]
+fn resize_db_test() {
+    // as saved by Redis 3.2
+    let rdb_file = [
+        &b"REDIS0007"[..],
+        &[0xfa, 0x09], b"redis-ver", &[0x05], b"3.2.0",          // aux redis-ver => 3.2.0
+        &[0xfa, 0x0a], b"redis-bits", &[0xc0, 0x40],             // aux redis-bits => 64
+        &[0xfa, 0x05], b"ctime", &[0xc2, 0x2c, 0x6b, 0x3a, 0x59], // aux ctime => 1497000748
+        &[0xfe, 0x00],                                           // <DatabaseNumber 0>
+        &[0xfb, 0x02, 0x01],                                     // RESIZEDB 2 keys, 1 expiring
+        &[0xfc, 0xe8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x61, 0x01, 0x31], // a => 1, expiring
+        &[0x00, 0x01, 0x62, 0x01, 0x32],                         // b => 2
+        &[0xfe, 0x01, 0xfb, 0x01, 0x00],                         // <DatabaseNumber 1>, RESIZEDB 1 key
+        &[0x00, 0x01, 0x63, 0x01, 0x33],                         // c => 3
+        &[0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // end of rdb, checksum
+    ].concat();
+
+    for r in [parse_rdb(&rdb_file).unwrap(), parse_rdb_skipping(&rdb_file).unwrap()] {
+        assert_eq!(r.1.len(), 3);
+        assert_eq!(r.databases().map(|db| (db.number(), db.records().count())).collect::<Vec<(u32, usize)>>(), vec![(0, 2), (1, 1)]);
+        // RESIZEDB is not written back
+        let mut bytes = Vec::new();
+        assert!(r.ser(&mut bytes).is_ok());
+        assert_eq!(bytes.len(), rdb_file.len() - 6);
     }
+    // every element is parsed up to the checksum
+    assert_eq!(error_offset(&rdb_file), rdb_file.len() - 8);
+
+    // a function library of RDB v10 before the databases
+    let rdb_file = [
+        &b"REDIS0010"[..],
+        &[0xf5, 0x04], b"code",                                  // FUNCTION2 code
+        &[0xfe, 0x00, 0xfb, 0x01, 0x00],                         // <DatabaseNumber 0>, RESIZEDB 1 key
+        &[0x00, 0x01, 0x61, 0x01, 0x31],                         // a => 1
+        &[0xff, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00], // end of rdb, checksum
+    ].concat();
+    assert_eq!(parse_rdb(&rdb_file).unwrap().databases().map(|db| db.records().count()).sum::<usize>(), 1);
+    assert!(parse_rdb(&[&b"REDIS0009"[..], &rdb_file[9..]].concat()).is_err());
 }
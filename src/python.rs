@@ -0,0 +1,198 @@
+// the code pyo3 generates for methods returning PyResult converts PyErr into itself
+#![allow(clippy::useless_conversion)]
+
+use std::collections::HashSet;
+
+use pyo3::prelude::*;
+use pyo3::exceptions::PyValueError;
+
+use super::error::{ Result, RmergerError };
+use super::file::{ ConflictStrategy, PartRDB };
+use super::parser::{ RDBDec, Record, parse_rdb };
+
+/// record of a `PyDatabase`
+#[pyclass(frozen)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PyRecord {
+    key:        Vec<u8>,
+    value_type: u8,
+    expiry_ms:  Option<u64>,
+}
+
+#[pymethods]
+impl PyRecord {
+    /// key as bytes
+    #[getter]
+    fn key(&self) -> &[u8] {
+        &self.key
+    }
+
+    /// value type byte, as in the RDB file
+    #[getter]
+    fn value_type(&self) -> u8 {
+        self.value_type
+    }
+
+    /// expiry as a unix timestamp in milliseconds, None if the key does not expire
+    #[getter]
+    fn expiry_ms(&self) -> Option<u64> {
+        self.expiry_ms
+    }
+
+    fn __repr__(&self) -> String {
+        format!("PyRecord(key={:?}, value_type={}, expiry_ms={:?})",
+                String::from_utf8_lossy(&self.key), self.value_type, self.expiry_ms)
+    }
+}
+
+impl PyRecord {
+    fn from_record(record: &Record) -> Result<Self> {
+        Ok(PyRecord {
+            key:        RDBDec::decode(&record.0)?,
+            value_type: record.1.value_type() as u8,
+            expiry_ms:  record.2.as_ref().map(|e| e.as_millis()),
+        })
+    }
+}
+
+/// database of a `PyRDB`
+#[pyclass(frozen)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PyDatabase {
+    number:  u32,
+    records: Vec<PyRecord>,
+}
+
+#[pymethods]
+impl PyDatabase {
+    /// database number
+    #[getter]
+    fn number(&self) -> u32 {
+        self.number
+    }
+
+    /// records in the order of the RDB file
+    #[getter]
+    fn records(&self) -> Vec<PyRecord> {
+        self.records.clone()
+    }
+
+    fn __len__(&self) -> usize {
+        self.records.len()
+    }
+
+    fn __repr__(&self) -> String {
+        format!("PyDatabase(number={}, records={})", self.number, self.records.len())
+    }
+}
+
+/// parsed RDB file, keeping its bytes for `merge` and `to_bytes`
+#[pyclass(frozen)]
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct PyRDB {
+    data:      Vec<u8>,
+    version:   u32,
+    databases: Vec<PyDatabase>,
+}
+
+#[pymethods]
+impl PyRDB {
+    /// parse the RDB file `data`, raising ValueError if it does not parse
+    #[staticmethod]
+    fn parse(data: &[u8]) -> PyResult<Self> {
+        PyRDB::from_bytes(data.to_vec()).map_err(value_error)
+    }
+
+    /// merge `rdbs` into one RDB, the first of duplicate keys winning as with the rmerger command
+    #[staticmethod]
+    fn merge(rdbs: Vec<PyRef<PyRDB>>) -> PyResult<Self> {
+        let rdbs: Vec<&PyRDB> = rdbs.iter().map(|r| &**r).collect();
+        PyRDB::merged(&rdbs).map_err(value_error)
+    }
+
+    /// RDB version of the file
+    #[getter]
+    fn version(&self) -> u32 {
+        self.version
+    }
+
+    /// databases in the order of the RDB file
+    #[getter]
+    fn databases(&self) -> Vec<PyDatabase> {
+        self.databases.clone()
+    }
+
+    /// the RDB file as bytes
+    fn to_bytes(&self) -> &[u8] {
+        &self.data
+    }
+
+    fn __repr__(&self) -> String {
+        let records: usize = self.databases.iter().map(|db| db.records.len()).sum();
+        format!("PyRDB(version={}, databases={}, records={})", self.version, self.databases.len(), records)
+    }
+}
+
+impl PyRDB {
+    /// parse `data` into owned records
+    pub fn from_bytes(data: Vec<u8>) -> Result<Self> {
+        let (version, databases) = {
+            let rdb = parse_rdb(&data)?;
+            let mut databases = Vec::new();
+            for db in rdb.databases() {
+                let records = db.records().map(PyRecord::from_record).collect::<Result<Vec<_>>>()?;
+                databases.push(PyDatabase { number: db.number(), records });
+            }
+            (rdb.0.number()?, databases)
+        };
+        Ok(PyRDB { data, version, databases })
+    }
+
+    /// merge `rdbs` in memory with the default settings of `PartRDB`
+    pub fn merged(rdbs: &[&PyRDB]) -> Result<Self> {
+        let mut part = PartRDB::new_unlocked(true, ConflictStrategy::FirstWins, std::env::temp_dir().to_string_lossy().to_string())?;
+        part.set_in_memory(true);
+        for rdb in rdbs {
+            part.write_rdb(parse_rdb(&rdb.data)?, &HashSet::new(), false)?;
+        }
+        part.close_part_files()?;
+        let mut out = Vec::new();
+        part.merge_to(&mut out)?;
+        PyRDB::from_bytes(out)
+    }
+}
+
+fn value_error(e: RmergerError) -> PyErr {
+    PyValueError::new_err(e.to_string())
+}
+
+/// the `rmerger` Python module
+#[pymodule]
+fn rmerger(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<PyRDB>()?;
+    m.add_class::<PyDatabase>()?;
+    m.add_class::<PyRecord>()?;
+    Ok(())
+}
+
+
+/// test
+#[test]
+fn python_test() {
+    use super::parser::{ TEST_RDB_A, TEST_RDB_B };
+
+    let a = PyRDB::from_bytes(TEST_RDB_A.to_vec()).unwrap();
+    assert_eq!(a.version, 6);
+    assert_eq!(a.databases.iter().map(|db| (db.number, db.records.len())).collect::<Vec<_>>(), vec![(0, 2), (2, 1)]);
+    assert_eq!(a.databases[0].records[1], PyRecord { key: b"l".to_vec(), value_type: 1, expiry_ms: None });
+    assert_eq!(a.databases[1].records[0], PyRecord { key: b"b".to_vec(), value_type: 0, expiry_ms: Some(1000) });
+    assert!(PyRDB::from_bytes(b"REDIS0006\xfe".to_vec()).is_err());
+
+    let b = PyRDB::from_bytes(TEST_RDB_B.to_vec()).unwrap();
+    let merged = PyRDB::merged(&[&a, &b]).unwrap();
+    let keys: Vec<(u32, Vec<u8>)> = merged.databases.iter()
+        .flat_map(|db| db.records.iter().map(move |r| (db.number, r.key.clone())))
+        .collect();
+    assert_eq!(keys.len(), 4);
+    assert!(keys.contains(&(0, b"c".to_vec())) && keys.contains(&(2, b"b".to_vec())));
+}
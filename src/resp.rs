@@ -0,0 +1,171 @@
+use std::io::{ Write, BufWriter };
+
+use super::error::Result;
+use super::parser::Record;
+use super::restore::{ dump_payload, quote };
+use super::value::{ DecodedRecord, DecodedValue };
+
+/// command name followed by its arguments
+pub type Command = Vec<Vec<u8>>;
+
+fn command(name: &str, key: &[u8], args: Vec<Vec<u8>>) -> Command {
+    let mut c = vec![name.as_bytes().to_vec(), key.to_vec()];
+    c.extend(args);
+    c
+}
+
+/// commands which recreate `record` from its decoded value, in any encoding: SET, DEL then RPUSH,
+/// SADD, ZADD or HSET, or RESTORE with REPLACE for a module value, then PEXPIREAT for a key with expiry
+pub fn record_commands(record: &Record) -> Result<Vec<Command>> {
+    let DecodedRecord { key, value, expiry_ms, .. } = DecodedRecord::decode(0, record)?;
+    // SET and RESTORE REPLACE overwrite the key, the others would add to an existing value
+    let del = || command("DEL", &key, vec![]);
+    let mut commands = match value {
+        DecodedValue::String(v)    => vec![command("SET", &key, vec![v])],
+        DecodedValue::List(v)      => vec![del(), command("RPUSH", &key, v)],
+        DecodedValue::Set(v)       => vec![del(), command("SADD", &key, v)],
+        DecodedValue::SortedSet(v) => {
+            vec![del(), command("ZADD", &key, v.into_iter().flat_map(|(member, score)| vec![score.to_string().into_bytes(), member]).collect())]
+        },
+        DecodedValue::Hash(v)      => {
+            vec![del(), command("HSET", &key, v.into_iter().flat_map(|(field, value)| vec![field, value]).collect())]
+        },
+        DecodedValue::Opaque { .. } => {
+            vec![command("RESTORE", &key, vec![b"0".to_vec(), dump_payload(&record.1)?, b"REPLACE".to_vec()])]
+        },
+    };
+    if let Some(ms) = expiry_ms {
+        commands.push(command("PEXPIREAT", &key, vec![ms.to_string().into_bytes()]));
+    }
+    Ok(commands)
+}
+
+/// write `args` as a RESP2 array of bulk strings
+pub fn write_command<W: Write>(w: &mut W, args: &[Vec<u8>]) -> Result<()> {
+    write!(w, "*{}\r\n", args.len())?;
+    for arg in args {
+        write!(w, "${}\r\n", arg.len())?;
+        w.write_all(arg)?;
+        w.write_all(b"\r\n")?;
+    }
+    Ok(())
+}
+
+/// write records as RESP2 commands, e.g. for `redis-cli --pipe`, selecting their database first
+pub struct RespCommandWriter<W: Write> {
+    out: BufWriter<W>,
+    db:  Option<u32>,
+}
+
+impl<W: Write> RespCommandWriter<W> {
+    pub fn new(w: W) -> Self {
+        RespCommandWriter { out: BufWriter::new(w), db: None }
+    }
+
+    pub fn write_record(&mut self, db: u32, record: &Record) -> Result<()> {
+        if self.db != Some(db) {
+            write_command(&mut self.out, &[b"SELECT".to_vec(), db.to_string().into_bytes()])?;
+            self.db = Some(db);
+        }
+        for c in record_commands(record)? {
+            write_command(&mut self.out, &c)?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+/// argument in redis-cli syntax, quoted only if it is empty or has other than printable ASCII
+/// or quotes and backslashes
+pub fn text_arg(bytes: &[u8]) -> String {
+    if !bytes.is_empty() && bytes.iter().all(|b| b.is_ascii_graphic() && !b"\"'\\".contains(b)) {
+        String::from_utf8_lossy(bytes).to_string()
+    } else {
+        quote(bytes)
+    }
+}
+
+/// write records as commands in redis-cli syntax, one per line, selecting their database first
+pub struct TextCommandWriter<W: Write> {
+    out: BufWriter<W>,
+    db:  Option<u32>,
+}
+
+impl<W: Write> TextCommandWriter<W> {
+    pub fn new(w: W) -> Self {
+        TextCommandWriter { out: BufWriter::new(w), db: None }
+    }
+
+    pub fn write_record(&mut self, db: u32, record: &Record) -> Result<()> {
+        if self.db != Some(db) {
+            writeln!(self.out, "SELECT {}", db)?;
+            self.db = Some(db);
+        }
+        for c in record_commands(record)? {
+            writeln!(self.out, "{}", c.iter().map(|a| text_arg(a)).collect::<Vec<String>>().join(" "))?;
+        }
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+
+/// test
+#[test]
+fn resp_command_writer_test() {
+    use super::parser::{ record, parse_done };
+
+    let mut w = RespCommandWriter::new(Vec::new());
+    // k => v, expiring at 1000 ms
+    let string = parse_done(record(&[0xfc, 0xe8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x6b, 0x01, 0x76])).unwrap();
+    assert!(w.write_record(1, &string).is_ok());
+    // z => { a: 1.5 }
+    let zset = parse_done(record(&[0x03, 0x01, 0x7a, 0x01, 0x01, 0x61, 0x03, 0x31, 0x2e, 0x35])).unwrap();
+    assert!(w.write_record(1, &zset).is_ok());
+    let out = w.out.into_inner().ok().unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), [
+        "*2\r\n$6\r\nSELECT\r\n$1\r\n1\r\n",
+        "*3\r\n$3\r\nSET\r\n$1\r\nk\r\n$1\r\nv\r\n",
+        "*3\r\n$9\r\nPEXPIREAT\r\n$1\r\nk\r\n$4\r\n1000\r\n",
+        "*2\r\n$3\r\nDEL\r\n$1\r\nz\r\n",
+        "*4\r\n$4\r\nZADD\r\n$1\r\nz\r\n$3\r\n1.5\r\n$1\r\na\r\n",
+    ].concat());
+}
+
+#[test]
+fn text_command_writer_test() {
+    use super::parser::{ record, parse_done };
+
+    assert_eq!(text_arg(b"user:1"), "user:1");
+    assert_eq!(text_arg(b""), "\"\"");
+    assert_eq!(text_arg(b"a b\xff"), "\"a b\\xff\"");
+
+    let mut w = TextCommandWriter::new(Vec::new());
+    // 0 => [1, 2], expiring at 1000 ms
+    let list = parse_done(record(&[0xfc, 0xe8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0x30, 0x02, 0x01, 0x31, 0x01, 0x32])).unwrap();
+    assert!(w.write_record(0, &list).is_ok());
+    let out = w.out.into_inner().ok().unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), "SELECT 0\nDEL 0\nRPUSH 0 1 2\nPEXPIREAT 0 1000\n");
+}
+
+#[test]
+fn record_commands_ziplist_test() {
+    use super::parser::{ record, parse_done, ziplist_encode };
+
+    let zl = ziplist_encode(&[b"f", b"v"]);
+    let mut bytes = vec![0x0d, 0x01, 0x68, zl.len() as u8]; // h => { f: v } as a ziplist
+    bytes.extend_from_slice(&zl);
+    let hash = parse_done(record(&bytes)).unwrap();
+    assert_eq!(record_commands(&hash).unwrap(), vec![
+        vec![b"DEL".to_vec(), b"h".to_vec()],
+        vec![b"HSET".to_vec(), b"h".to_vec(), b"f".to_vec(), b"v".to_vec()],
+    ]);
+}
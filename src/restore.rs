@@ -0,0 +1,89 @@
+use std::io::{ Write, BufWriter };
+
+use super::base64;
+use super::error::Result;
+use super::crc64::Crc64;
+use super::parser::{ RDBSer, RDBDec, Record, EncodedValue };
+
+// RDB version stamped on DUMP payloads
+const DUMP_RDB_VERSION: u16 = 6;
+
+/// serialized value in the format of the DUMP command:
+/// type, value, RDB version and CRC64, the last two little-endian
+pub fn dump_payload(val: &EncodedValue) -> Result<Vec<u8>> {
+    let mut v = vec![val.value_type() as u8];
+    val.ser(&mut v)?;
+    v.write_all(&[DUMP_RDB_VERSION as u8, (DUMP_RDB_VERSION >> 8) as u8])?;
+    let mut crc = Crc64::new();
+    crc.update(&v);
+    let digest = crc.digest();
+    v.extend((0..8).map(|i| (digest >> (8 * i)) as u8));
+    Ok(v)
+}
+
+/// double-quoted argument in redis-cli syntax
+pub fn quote(bytes: &[u8]) -> String {
+    let mut out = String::with_capacity(bytes.len() + 2);
+    out.push('"');
+    for b in bytes {
+        match *b {
+            b'"'  => out.push_str("\\\""),
+            b'\\' => out.push_str("\\\\"),
+            b'\n' => out.push_str("\\n"),
+            b'\r' => out.push_str("\\r"),
+            b'\t' => out.push_str("\\t"),
+            0x20..=0x7e => out.push(*b as char),
+            _ => out.push_str(&format!("\\x{:02x}", b)),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// write records as `RESTORE <key> <ttl> <base64 DUMP payload>` lines
+pub struct RestoreCommandWriter<W: Write> {
+    out: BufWriter<W>,
+    db:  Option<u32>,
+}
+
+impl<W: Write> RestoreCommandWriter<W> {
+    pub fn new(w: W) -> Self {
+        RestoreCommandWriter { out: BufWriter::new(w), db: None }
+    }
+
+    /// the TTL is relative to `now_ms`; records which have already expired are skipped
+    pub fn write_record(&mut self, db: u32, record: &Record, now_ms: u64) -> Result<()> {
+        let &Record(ref key, ref val, expiry) = record;
+        let expiry_ms = match expiry {
+            Some(e) => Some(e.checked_millis()?),
+            None    => None,
+        };
+        let ttl = match expiry_ms {
+            Some(ms) if ms <= now_ms => return Ok(()),
+            Some(ms) => ms - now_ms,
+            None     => 0,
+        };
+
+        if self.db != Some(db) {
+            writeln!(self.out, "SELECT {}", db)?;
+            self.db = Some(db);
+        }
+
+        let key: Vec<u8> = RDBDec::decode(key)?;
+        writeln!(self.out, "RESTORE {} {} {}", quote(&key), ttl, base64::encode(&dump_payload(val)?))?;
+        Ok(())
+    }
+
+    pub fn finish(mut self) -> Result<()> {
+        self.out.flush()?;
+        Ok(())
+    }
+}
+
+
+/// test
+#[test]
+fn quote_test() {
+    assert_eq!(quote(b"user:1"), "\"user:1\"");
+    assert_eq!(quote(b"a \"b\"\n\x00"), "\"a \\\"b\\\"\\n\\x00\"");
+}
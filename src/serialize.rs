@@ -0,0 +1,198 @@
+use serde::ser::{ Serialize, Serializer };
+
+use super::base64;
+use super::parser::{
+    RDB, Database, DatabaseNumber, Record, RDBVersion, AuxField, Checksum, ExpiryTime,
+    EncodedLength, EncodedString, EncodedValue, EncodedList, EncodedSet, EncodedSortedset, EncodedHashmap, EncodedModule,
+    EncodedZiplist, EncodedIntset, EncodedSortedsetZiplist, EncodedHashmapZiplist,
+};
+
+// serde::Serialize for the parser types, as derived: tuple structs as arrays and enums as
+// `{"Variant": ...}`, but with the bytes of the file as base64 strings
+
+type SerResult<S> = ::std::result::Result<<S as Serializer>::Ok, <S as Serializer>::Error>;
+
+/// bytes as a base64 string
+struct B64<'a>(&'a [u8]);
+
+impl<'a> Serialize for B64<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        s.serialize_str(&base64::encode(self.0))
+    }
+}
+
+impl<'a> Serialize for EncodedLength<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        match self {
+            &EncodedLength::I(n, b) => s.serialize_newtype_variant("EncodedLength", 0, "I", &(n, B64(b))),
+            &EncodedLength::S(n, b) => s.serialize_newtype_variant("EncodedLength", 1, "S", &(n, B64(b))),
+        }
+    }
+}
+
+impl<'a> Serialize for EncodedString<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        match self {
+            &EncodedString::Raw(l, b) => s.serialize_newtype_variant("EncodedString", 0, "Raw", &(l, B64(b))),
+            &EncodedString::Int(l, b) => s.serialize_newtype_variant("EncodedString", 1, "Int", &(l, B64(b))),
+            &EncodedString::Lzf(cl, l, ul, b) =>
+                s.serialize_newtype_variant("EncodedString", 2, "Lzf", &(cl, l, ul, B64(b))),
+        }
+    }
+}
+
+impl<'a> Serialize for EncodedList<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        (&self.0, &self.1).serialize(s)
+    }
+}
+
+impl<'a> Serialize for EncodedSet<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        (&self.0, &self.1).serialize(s)
+    }
+}
+
+/// members as `[member, score length, score]`
+impl<'a> Serialize for EncodedSortedset<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        let members: Vec<_> = self.1.iter().map(|&(ref m, n, score)| (m, n, B64(score))).collect();
+        (&self.0, members).serialize(s)
+    }
+}
+
+impl<'a> Serialize for EncodedHashmap<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        (&self.0, &self.1).serialize(s)
+    }
+}
+
+/// as `[module type id, bytes]`
+impl<'a> Serialize for EncodedModule<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        (self.0, B64(self.1)).serialize(s)
+    }
+}
+
+impl<'a> Serialize for EncodedZiplist<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        self.0.serialize(s)
+    }
+}
+
+impl<'a> Serialize for EncodedIntset<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        self.0.serialize(s)
+    }
+}
+
+impl<'a> Serialize for EncodedSortedsetZiplist<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        self.0.serialize(s)
+    }
+}
+
+impl<'a> Serialize for EncodedHashmapZiplist<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        self.0.serialize(s)
+    }
+}
+
+/// tagged by the value type, e.g. `{"V1": [length, [strings]]}` for a list
+impl<'a> Serialize for EncodedValue<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        match self {
+            &EncodedValue::V0(ref v) => s.serialize_newtype_variant("EncodedValue", 0, "V0", v),
+            &EncodedValue::V1(ref v) => s.serialize_newtype_variant("EncodedValue", 1, "V1", v),
+            &EncodedValue::V2(ref v) => s.serialize_newtype_variant("EncodedValue", 2, "V2", v),
+            &EncodedValue::V3(ref v) => s.serialize_newtype_variant("EncodedValue", 3, "V3", v),
+            &EncodedValue::V4(ref v) => s.serialize_newtype_variant("EncodedValue", 4, "V4", v),
+            &EncodedValue::V7(ref v) => s.serialize_newtype_variant("EncodedValue", 5, "V7", v),
+            &EncodedValue::VA(ref v) => s.serialize_newtype_variant("EncodedValue", 6, "VA", v),
+            &EncodedValue::VB(ref v) => s.serialize_newtype_variant("EncodedValue", 7, "VB", v),
+            &EncodedValue::VC(ref v) => s.serialize_newtype_variant("EncodedValue", 8, "VC", v),
+            &EncodedValue::VD(ref v) => s.serialize_newtype_variant("EncodedValue", 9, "VD", v),
+        }
+    }
+}
+
+impl<'a> Serialize for ExpiryTime<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        match self {
+            &ExpiryTime::MilliSec(b) => s.serialize_newtype_variant("ExpiryTime", 0, "MilliSec", &B64(b)),
+            &ExpiryTime::Sec(b)      => s.serialize_newtype_variant("ExpiryTime", 1, "Sec", &B64(b)),
+        }
+    }
+}
+
+impl<'a> Serialize for Record<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        (&self.0, &self.1, &self.2).serialize(s)
+    }
+}
+
+impl<'a> Serialize for DatabaseNumber<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        (&self.0, self.1).serialize(s)
+    }
+}
+
+impl<'a> Serialize for Database<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        (&self.0, &self.1).serialize(s)
+    }
+}
+
+impl Serialize for RDBVersion {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        B64(&self.0).serialize(s)
+    }
+}
+
+impl<'a> Serialize for AuxField<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        (&self.0, &self.1).serialize(s)
+    }
+}
+
+impl<'a> Serialize for Checksum<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        B64(self.0).serialize(s)
+    }
+}
+
+impl<'a> Serialize for RDB<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> SerResult<S> {
+        (&self.0, &self.1, &self.2, &self.3).serialize(s)
+    }
+}
+
+
+/// test
+#[test]
+fn rdb_serialize_test() {
+    use serde_json::{ self, Value };
+    use super::parser::parse_rdb;
+
+    let case = [
+        0x52, 0x45, 0x44, 0x49, 0x53, 0x30, 0x30, 0x30, 0x36, // REDIS0006
+        0xfe, 0x00,                                           // <DatabaseNumber 0>
+        0x00, 0x01, 0x6b, 0xc0, 0x05,                         // k => 5
+        0x01, 0x01, 0x6c, 0x01, 0x01, 0x61,                   // l => [a]
+        0xff,                                                 // end of rdb
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00        // checksum
+    ];
+    let r = parse_rdb(&case).unwrap();
+    let json = serde_json::to_string(&r).unwrap();
+    let value: Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(value, serde_json::json!([
+        "MDAwNg==",
+        [],
+        [[[{ "I": [0, "AA=="] }, 0], [
+            [{ "Raw": [{ "I": [1, "AQ=="] }, "aw=="] }, { "V0": { "Int": [{ "S": [0, "wA=="] }, "BQ=="] } }, null],
+            [{ "Raw": [{ "I": [1, "AQ=="] }, "bA=="] }, { "V1": [{ "I": [1, "AQ=="] }, [{ "Raw": [{ "I": [1, "AQ=="] }, "YQ=="] }]] }, null],
+        ]]],
+        "AAAAAAAAAAA=",
+    ]));
+    assert_eq!(serde_json::to_string(&value).unwrap(), json);
+}
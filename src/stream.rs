@@ -0,0 +1,235 @@
+use nom::Err;
+
+#[cfg(test)] use super::parser::RDBSer;
+
+use std::io::Read;
+
+use super::error::{ RmergerError, Result };
+use super::parser::{
+    rdb_version, record, database_number, aux_field, opcode, end_of_rdb, hex_dump,
+    RDBDec, OwnedRecord, RDBVersion, DatabaseNumber, AuxField,
+};
+
+/// item of an RDB file, in file order
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum Event {
+    Version(Vec<u8>),
+    Aux(Vec<u8>, Vec<u8>),
+    SelectDb(u32),
+    Record(OwnedRecord),
+    End(Option<Vec<u8>>),
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+enum State {
+    Header,
+    Body,
+    Checksum,
+    Done,
+}
+
+const READ_CHUNK_SIZE: usize = 64 * 1024;
+const CHECKSUM_SIZE:   usize = 8;
+
+/// parse an RDB file incrementally, keeping only the unparsed part of the input in memory; module
+/// aux data, function libraries and RESIZEDB are skipped
+pub struct RdbReader<R: Read> {
+    reader:  R,
+    buf:     Vec<u8>,
+    pos:     usize,
+    // input bytes dropped from the buffer
+    dropped: usize,
+    eof:     bool,
+    state:   State,
+}
+
+impl<R: Read> RdbReader<R> {
+    pub fn new(reader: R) -> Self {
+        RdbReader {
+            reader,
+            buf:     Vec::new(),
+            pos:     0,
+            dropped: 0,
+            eof:     false,
+            state:   State::Header,
+        }
+    }
+
+    /// `error` at the current input offset
+    fn parse_error(&self, error: RmergerError) -> RmergerError {
+        RmergerError::ParseErrorAt { offset: self.dropped + self.pos, dump: hex_dump(&self.buf, self.pos), error: Box::new(error) }
+    }
+
+    /// read more input, dropping the consumed part of the buffer
+    fn refill(&mut self) -> Result<()> {
+        assert_result!(!self.eof, self.parse_error(RmergerError::ParseIncomplete));
+        self.buf.drain(..self.pos);
+        self.dropped += self.pos;
+        self.pos = 0;
+
+        let len = self.buf.len();
+        self.buf.resize(len + READ_CHUNK_SIZE, 0);
+        let n = self.reader.read(&mut self.buf[len..])?;
+        self.buf.truncate(len + n);
+        self.eof = n == 0;
+        Ok(())
+    }
+
+    /// try to parse one event from the buffered input; `None` means more input is needed
+    fn parse(&mut self) -> Result<Option<Event>> {
+        loop {
+            // the event, the bytes it takes and the state after it
+            let (event, consumed, state) = {
+                let input = &self.buf[self.pos..];
+                let done = |rest: &[u8]| input.len() - rest.len();
+                match self.state {
+                    State::Header => match rdb_version(input) {
+                        Ok((rest, RDBVersion(v))) => (Some(Event::Version(v.to_vec())), done(rest), State::Body),
+                        Err(Err::Incomplete(_)) => return Ok(None),
+                        Err(Err::Error(e)) | Err(Err::Failure(e)) => return Err(self.parse_error(e.into())),
+                    },
+                    State::Body => match record(input) {
+                        Ok((rest, r)) => (Some(Event::Record(OwnedRecord::from(&r))), done(rest), State::Body),
+                        Err(Err::Incomplete(_)) => return Ok(None),
+                        Err(_) => match database_number(input) {
+                            Ok((rest, DatabaseNumber(_, n))) => (Some(Event::SelectDb(n)), done(rest), State::Body),
+                            Err(Err::Incomplete(_)) => return Ok(None),
+                            Err(_) => match aux_field(input) {
+                                Ok((rest, AuxField(k, v))) => (Some(Event::Aux(RDBDec::decode(&k)?, RDBDec::decode(&v)?)), done(rest), State::Body),
+                                Err(Err::Incomplete(_)) => return Ok(None),
+                                // RESIZEDB, module aux data and function libraries
+                                Err(_) => match opcode(input) {
+                                    Ok((rest, _)) => (None, done(rest), State::Body),
+                                    Err(Err::Incomplete(_)) => return Ok(None),
+                                    Err(_) => match end_of_rdb(input) {
+                                        Ok((rest, _)) => (None, done(rest), State::Checksum),
+                                        Err(Err::Incomplete(_)) => return Ok(None),
+                                        Err(Err::Error(e)) | Err(Err::Failure(e)) => return Err(self.parse_error(e.into())),
+                                    },
+                                },
+                            },
+                        },
+                    },
+                    State::Checksum => {
+                        if input.len() < CHECKSUM_SIZE && !self.eof {
+                            return Ok(None);
+                        }
+                        assert_result!(input.is_empty() || input.len() == CHECKSUM_SIZE,
+                                       self.parse_error(RmergerError::Parse("invalid checksum length".to_string())));
+                        let checksum = if input.is_empty() { None } else { Some(input.to_vec()) };
+                        (Some(Event::End(checksum)), input.len(), State::Done)
+                    },
+                    State::Done => unreachable!(),
+                }
+            };
+
+            self.pos += consumed;
+            self.state = state;
+            if event.is_some() {
+                return Ok(event);
+            }
+        }
+    }
+}
+
+impl<R: Read> Iterator for RdbReader<R> {
+    type Item = Result<Event>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.state != State::Done {
+            match self.parse() {
+                Ok(Some(event)) => return Some(Ok(event)),
+                Ok(None) => if let Err(e) = self.refill() {
+                    self.state = State::Done;
+                    return Some(Err(e));
+                },
+                Err(e) => {
+                    self.state = State::Done;
+                    return Some(Err(e));
+                },
+            }
+        }
+        None
+    }
+}
+
+
+/// test
+#[cfg(test)]
+struct ByteReader<'a>(&'a [u8]);
+
+#[cfg(test)]
+impl<'a> Read for ByteReader<'a> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = if self.0.is_empty() || buf.is_empty() { 0 } else { 1 };
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn rdb_reader_test() {
+    let case_1 = [
+        0x52, 0x45, 0x44, 0x49, 0x53, 0x30, 0x30, 0x30, 0x36, // REDIS0006
+        0xfe, 0x00,                                           // <DatabaseNumber 0>
+        0x00, 0x01, 0x30, 0x01, 0x31,                         // 0 => 1
+        0xfe, 0x01,                                           // <DatabaseNumber 1>
+        0xfd, 0x00, 0x00, 0x00, 0xff,
+        0x00, 0x01, 0x31, 0x01, 0x32,                         // 1 => 2
+        0xff,                                                 // end of rdb
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00        // checksum
+    ];
+    let events: Vec<Event> = RdbReader::new(ByteReader(&case_1[..])).map(|e| e.unwrap()).collect();
+    assert_eq!(events.len(), 6);
+    assert_eq!(events[0], Event::Version(b"0006".to_vec()));
+    assert_eq!(events[1], Event::SelectDb(0));
+    assert_eq!(events[3], Event::SelectDb(1));
+    assert_eq!(events[5], Event::End(Some(vec![0x00; 8])));
+    match events[4] {
+        Event::Record(ref r) => {
+            let mut v = Vec::new();
+            assert!(r.record().ser(&mut v).is_ok());
+            assert_eq!(&v[..], &case_1[18..28]);
+        },
+        _ => panic!(),
+    }
+
+    let truncated: Vec<Result<Event>> = RdbReader::new(ByteReader(&case_1[..20])).collect();
+    assert_eq!(truncated.len(), 5);
+    assert!(match truncated[4] {
+        Err(RmergerError::ParseErrorAt { offset, ref error, .. }) => offset == 18 && matches!(**error, RmergerError::ParseIncomplete),
+        _                                                         => false,
+    });
+    // the offset of an error counts the input read before it
+    let mut broken = case_1;
+    broken[23] = 0x77;
+    let events: Vec<Result<Event>> = RdbReader::new(ByteReader(&broken[..])).collect();
+    assert!(match events.last() {
+        Some(&Err(RmergerError::ParseErrorAt { offset, .. })) => offset == 18,
+        _                                                     => false,
+    });
+
+    let case_2 = [
+        0x52, 0x45, 0x44, 0x49, 0x53, 0x30, 0x30, 0x30, 0x37, // REDIS0007
+        0xfe, 0x00, 0xfb, 0x01, 0x00,                         // <DatabaseNumber 0>, RESIZEDB 1 key
+        0x00, 0x01, 0x30, 0x01, 0x31,                         // 0 => 1
+        0xff,                                                 // end of rdb
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00        // checksum
+    ];
+    let events: Vec<Event> = RdbReader::new(ByteReader(&case_2[..])).map(|e| e.unwrap()).collect();
+    assert_eq!(events.len(), 4);
+    assert_eq!(events[1], Event::SelectDb(0));
+
+    let case_3 = [
+        0x52, 0x45, 0x44, 0x49, 0x53, 0x30, 0x30, 0x31, 0x30, // REDIS0010
+        0xf5, 0x02, 0x66, 0x6e,                               // function library "fn"
+        0xfe, 0x00,                                           // <DatabaseNumber 0>
+        0x00, 0x01, 0x30, 0x01, 0x31,                         // 0 => 1
+        0xff,                                                 // end of rdb
+        0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00        // checksum
+    ];
+    let events: Vec<Event> = RdbReader::new(ByteReader(&case_3[..])).map(|e| e.unwrap()).collect();
+    assert_eq!(events.len(), 4);
+    assert_eq!(events[1], Event::SelectDb(0));
+}
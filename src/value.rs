@@ -0,0 +1,235 @@
+#[cfg(feature = "serde")] use serde::ser::{ Serialize, Serializer, SerializeMap, SerializeSeq, SerializeStruct };
+
+#[cfg(feature = "serde")] use super::base64;
+use super::error::{ RmergerError, Result };
+use super::parser::{
+    RDBDec, Record, EncodedValue, EncodedString,
+    EncodedList, EncodedSet, EncodedSortedset, EncodedHashmap, EncodedModule,
+};
+use super::parser::EncodedValue::*;
+
+/// value of a record decoded out of its encoding
+#[derive(Debug, PartialEq, Clone)]
+pub enum DecodedValue {
+    String(Vec<u8>),
+    List(Vec<Vec<u8>>),
+    Set(Vec<Vec<u8>>),
+    /// (member, score) pairs
+    SortedSet(Vec<(Vec<u8>, f64)>),
+    /// (field, value) pairs
+    Hash(Vec<(Vec<u8>, Vec<u8>)>),
+    /// value of `type_name` which is not decoded, a module value, as its raw bytes
+    Opaque { type_name: &'static str, encoding: &'static str, bytes: Vec<u8> },
+}
+
+fn decode_all(v: &[EncodedString]) -> Result<Vec<Vec<u8>>> {
+    v.iter().map(RDBDec::decode).collect()
+}
+
+fn score(s: &[u8]) -> Result<f64> {
+    let score = String::from_utf8_lossy(s);
+    score.parse::<f64>().map_err(|_| RmergerError::Parse(format!("invalid sorted set score: {}", score)))
+}
+
+impl DecodedValue {
+    /// the value in any encoding, a ziplist or an intset expanded as the plain encoding of its type
+    pub fn decode(val: &EncodedValue) -> Result<Self> {
+        match val {
+            &V0(ref s)                        => Ok(DecodedValue::String(RDBDec::decode(s)?)),
+            &V1(EncodedList(_, ref v))        => Ok(DecodedValue::List(decode_all(v)?)),
+            &V2(EncodedSet(_, ref v))         => Ok(DecodedValue::Set(decode_all(v)?)),
+            &V3(EncodedSortedset(_, ref v))   => {
+                let mut members = Vec::new();
+                for &(ref member, _, s) in v {
+                    members.push((RDBDec::decode(member)?, score(s)?));
+                }
+                Ok(DecodedValue::SortedSet(members))
+            },
+            &V4(EncodedHashmap(_, ref v))     => {
+                let fields = v.iter()
+                    .map(|&(ref field, ref value)| Ok((RDBDec::decode(field)?, RDBDec::decode(value)?)))
+                    .collect::<Result<_>>()?;
+                Ok(DecodedValue::Hash(fields))
+            },
+            &V7(EncodedModule(_, bytes))      =>
+                Ok(DecodedValue::Opaque { type_name: "module", encoding: "module", bytes: bytes.to_vec() }),
+            &VA(ref z)                        => Ok(DecodedValue::List(RDBDec::decode(z)?)),
+            &VB(ref i)                        => {
+                let members: Vec<i64> = RDBDec::decode(i)?;
+                Ok(DecodedValue::Set(members.iter().map(|n| n.to_string().into_bytes()).collect()))
+            },
+            &VC(ref z)                        => {
+                let pairs: Vec<(Vec<u8>, Vec<u8>)> = RDBDec::decode(z)?;
+                let members = pairs.into_iter().map(|(member, s)| Ok((member, score(&s)?))).collect::<Result<_>>()?;
+                Ok(DecodedValue::SortedSet(members))
+            },
+            &VD(ref z)                        => Ok(DecodedValue::Hash(RDBDec::decode(z)?)),
+        }
+    }
+
+    /// value type as in Redis TYPE: string, list, set, zset or hash, or module for any module type
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            &DecodedValue::String(_)    => "string",
+            &DecodedValue::List(_)      => "list",
+            &DecodedValue::Set(_)       => "set",
+            &DecodedValue::SortedSet(_) => "zset",
+            &DecodedValue::Hash(_)      => "hash",
+            &DecodedValue::Opaque { type_name, .. } => type_name,
+        }
+    }
+}
+
+/// record of database `db` with its key and value decoded
+#[derive(Debug, PartialEq, Clone)]
+pub struct DecodedRecord {
+    pub db:        u32,
+    pub key:       Vec<u8>,
+    pub value:     DecodedValue,
+    pub expiry_ms: Option<u64>,
+}
+
+impl DecodedRecord {
+    pub fn decode(db: u32, record: &Record) -> Result<Self> {
+        let &Record(ref key, ref val, expiry) = record;
+        Ok(DecodedRecord {
+            db,
+            key:       RDBDec::decode(key)?,
+            value:     DecodedValue::decode(val)?,
+            expiry_ms: match expiry {
+                Some(e) => Some(e.checked_millis()?),
+                None    => None,
+            },
+        })
+    }
+
+    /// the record as one line of JSON: `{"db":0,"key":"k","type":"string","value":"v","expiry_ms":null}`
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> Result<String> {
+        ::serde_json::to_string(self).map_err(|e| RmergerError::Io(e.into()))
+    }
+}
+
+/// bytes serialized as a string, invalid UTF-8 replaced by U+FFFD
+#[cfg(feature = "serde")]
+struct Text<'a>(&'a [u8]);
+
+#[cfg(feature = "serde")]
+impl<'a> Serialize for Text<'a> {
+    fn serialize<S: Serializer>(&self, s: S) -> ::std::result::Result<S::Ok, S::Error> {
+        s.serialize_str(&String::from_utf8_lossy(self.0))
+    }
+}
+
+/// sorted set score, as a string if it is infinite or NaN, which JSON numbers cannot hold
+#[cfg(feature = "serde")]
+struct Score(f64);
+
+#[cfg(feature = "serde")]
+impl Serialize for Score {
+    fn serialize<S: Serializer>(&self, s: S) -> ::std::result::Result<S::Ok, S::Error> {
+        if self.0.is_finite() { s.serialize_f64(self.0) } else { s.serialize_str(&self.0.to_string()) }
+    }
+}
+
+/// strings, lists and sets as arrays, hashes as objects, sorted sets as `[member, score]` pairs
+/// and module values, which are not decoded, as `{"encoding": ..., "base64": ...}`
+#[cfg(feature = "serde")]
+impl Serialize for DecodedValue {
+    fn serialize<S: Serializer>(&self, s: S) -> ::std::result::Result<S::Ok, S::Error> {
+        match self {
+            &DecodedValue::String(ref v) => Text(v).serialize(s),
+            &DecodedValue::List(ref v) | &DecodedValue::Set(ref v) => {
+                let mut seq = s.serialize_seq(Some(v.len()))?;
+                for e in v {
+                    seq.serialize_element(&Text(e))?;
+                }
+                seq.end()
+            },
+            &DecodedValue::SortedSet(ref v) => {
+                let mut seq = s.serialize_seq(Some(v.len()))?;
+                for &(ref member, score) in v {
+                    seq.serialize_element(&(Text(member), Score(score)))?;
+                }
+                seq.end()
+            },
+            &DecodedValue::Hash(ref v) => {
+                let mut map = s.serialize_map(Some(v.len()))?;
+                for &(ref field, ref value) in v {
+                    map.serialize_entry(&Text(field), &Text(value))?;
+                }
+                map.end()
+            },
+            &DecodedValue::Opaque { encoding, ref bytes, .. } => {
+                let mut map = s.serialize_map(Some(2))?;
+                map.serialize_entry("encoding", encoding)?;
+                map.serialize_entry("base64", &base64::encode(bytes))?;
+                map.end()
+            },
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for DecodedRecord {
+    fn serialize<S: Serializer>(&self, s: S) -> ::std::result::Result<S::Ok, S::Error> {
+        let mut st = s.serialize_struct("DecodedRecord", 5)?;
+        st.serialize_field("db", &self.db)?;
+        st.serialize_field("key", &Text(&self.key))?;
+        st.serialize_field("type", self.value.type_name())?;
+        st.serialize_field("value", &self.value)?;
+        st.serialize_field("expiry_ms", &self.expiry_ms)?;
+        st.end()
+    }
+}
+
+
+/// test
+#[cfg(test)]
+fn decoded(bytes: &[u8]) -> DecodedRecord {
+    use super::parser::{ record, parse_done };
+
+    DecodedRecord::decode(0, &parse_done(record(bytes)).unwrap()).unwrap()
+}
+
+#[test]
+fn decoded_record_test() {
+    // 0 => [1, 2], expiring at 1000 ms
+    let list = decoded(&[0xfc, 0xe8, 0x03, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x01, 0x30, 0x02, 0x01, 0x31, 0x01, 0x32]);
+    assert_eq!(list.key, b"0".to_vec());
+    assert_eq!(list.value, DecodedValue::List(vec![b"1".to_vec(), b"2".to_vec()]));
+    assert_eq!(list.expiry_ms, Some(1000));
+
+    // z => { a: 1.5 }
+    let zset = decoded(&[0x03, 0x01, 0x7a, 0x01, 0x01, 0x61, 0x03, 0x31, 0x2e, 0x35]);
+    assert_eq!(zset.value, DecodedValue::SortedSet(vec![(b"a".to_vec(), 1.5)]));
+    assert_eq!(zset.value.type_name(), "zset");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn decoded_record_json_test() {
+    // h => { f: v }
+    let hash = decoded(&[0x04, 0x01, 0x68, 0x01, 0x01, 0x66, 0x01, 0x76]);
+    assert_eq!(hash.to_json().unwrap(), r#"{"db":0,"key":"h","type":"hash","value":{"f":"v"},"expiry_ms":null}"#);
+    // z => { a: 1.5 }
+    let zset = decoded(&[0x03, 0x01, 0x7a, 0x01, 0x01, 0x61, 0x03, 0x31, 0x2e, 0x35]);
+    assert_eq!(zset.to_json().unwrap(), r#"{"db":0,"key":"z","type":"zset","value":[["a",1.5]],"expiry_ms":null}"#);
+}
+
+#[test]
+fn decoded_compact_test() {
+    use super::parser::ziplist_encode;
+
+    let record = |t: u8, payload: &[u8]| -> Vec<u8> {
+        let mut bytes = vec![t, 0x01, 0x6b, payload.len() as u8]; // k => payload
+        bytes.extend_from_slice(payload);
+        bytes
+    };
+    assert_eq!(decoded(&record(0x0a, &ziplist_encode(&[b"x", b"7"]))).value, DecodedValue::List(vec![b"x".to_vec(), b"7".to_vec()]));
+    // intset of the 2-byte elements 1 and -2
+    assert_eq!(decoded(&record(0x0b, &[0x02, 0, 0, 0, 0x02, 0, 0, 0, 0x01, 0x00, 0xfe, 0xff])).value,
+               DecodedValue::Set(vec![b"1".to_vec(), b"-2".to_vec()]));
+    assert_eq!(decoded(&record(0x0c, &ziplist_encode(&[b"a", b"1.5"]))).value, DecodedValue::SortedSet(vec![(b"a".to_vec(), 1.5)]));
+    assert_eq!(decoded(&record(0x0d, &ziplist_encode(&[b"f", b"v"]))).value, DecodedValue::Hash(vec![(b"f".to_vec(), b"v".to_vec())]));
+}
@@ -0,0 +1,85 @@
+use js_sys::{ Array, Object, Reflect };
+use wasm_bindgen::prelude::*;
+
+use super::csv::element_count;
+use super::error::Result;
+use super::parser::{ RDBDec, RDBSer, parse_rdb as parse };
+
+/// record of `parse_rdb`, as shown in www/
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct WasmRecord {
+    pub db:         u32,
+    /// key, lossy only for invalid UTF-8
+    pub key:        String,
+    /// value type with its encoding, e.g. hashmap-ziplist
+    pub value_type: String,
+    pub elements:   u64,
+    /// serialized size of the record in bytes
+    pub bytes:      usize,
+    pub expiry_ms:  Option<u64>,
+}
+
+/// RDB version, database numbers and records of the RDB file `data`
+pub fn inspect(data: &[u8]) -> Result<(u32, Vec<u32>, Vec<WasmRecord>)> {
+    let rdb = parse(data)?;
+    let databases = rdb.databases().map(|db| db.number()).collect();
+    let mut records = Vec::new();
+    for (num, record) in &rdb {
+        let key: Vec<u8> = RDBDec::decode(&record.0)?;
+        let expiry_ms = match record.2 {
+            Some(e) => Some(e.checked_millis()?),
+            None    => None,
+        };
+        records.push(WasmRecord {
+            db:         num.1,
+            key:        String::from_utf8_lossy(&key).to_string(),
+            value_type: record.1.value_type().to_string(),
+            elements:   element_count(&record.1)?,
+            bytes:      record.ser(&mut std::io::sink())?,
+            expiry_ms,
+        });
+    }
+    Ok((rdb.0.number()?, databases, records))
+}
+
+fn set(obj: &Object, key: &str, value: JsValue) -> std::result::Result<(), JsValue> {
+    Reflect::set(obj, &JsValue::from_str(key), &value).map(|_| ())
+}
+
+/// parse the RDB file `data` into `{ version, databases, records }`, `databases` the database
+/// numbers and `records` objects of `db`, `key`, `type`, `elements`, `bytes` and `expiry_ms`,
+/// null if the key does not expire; throws the parse error as a string
+#[wasm_bindgen]
+pub fn parse_rdb(data: &[u8]) -> std::result::Result<JsValue, JsValue> {
+    let (version, databases, records) = inspect(data).map_err(|e| JsValue::from_str(&e.to_string()))?;
+    let rdb = Object::new();
+    set(&rdb, "version", JsValue::from(version))?;
+    set(&rdb, "databases", databases.into_iter().map(JsValue::from).collect::<Array>().into())?;
+    let array = Array::new();
+    for r in records {
+        let record = Object::new();
+        set(&record, "db", JsValue::from(r.db))?;
+        set(&record, "key", JsValue::from(r.key))?;
+        set(&record, "type", JsValue::from(r.value_type))?;
+        // numbers of JavaScript are exact up to 2^53
+        set(&record, "elements", JsValue::from(r.elements as f64))?;
+        set(&record, "bytes", JsValue::from(r.bytes as f64))?;
+        set(&record, "expiry_ms", r.expiry_ms.map_or(JsValue::NULL, |ms| JsValue::from(ms as f64)))?;
+        array.push(&record);
+    }
+    set(&rdb, "records", array.into())?;
+    Ok(rdb.into())
+}
+
+
+/// test
+#[test]
+fn inspect_test() {
+    let file = super::parser::TEST_RDB_A;
+    let (version, databases, records) = inspect(file).unwrap();
+    assert_eq!((version, databases), (6, vec![0, 2]));
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[1], WasmRecord { db: 0, key: "l".to_string(), value_type: "list".to_string(), elements: 1, bytes: 6, expiry_ms: None });
+    assert_eq!((records[2].db, records[2].bytes, records[2].expiry_ms), (2, 14, Some(1000)));
+    assert!(inspect(&file[..12]).is_err());
+}
@@ -0,0 +1,248 @@
+extern crate rmerger;
+extern crate flate2;
+#[cfg(feature = "serde")]
+extern crate serde_json;
+
+use std::fs;
+use std::io::Write;
+use std::path::PathBuf;
+use std::process::{ self, Command, Output, Stdio };
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use rmerger::parser::{ parse_rdb, RDBDec };
+
+// a => 1 and l => [x] in database 0, b => 2 expiring at 1000 ms in database 2
+const A: &[u8] = b"REDIS0006\xfe\x00\x00\x01a\x011\x01\x01l\x01\x01x\xfe\x02\xfc\xe8\x03\x00\x00\x00\x00\x00\x00\x00\x01b\x012\xff\x00\x00\x00\x00\x00\x00\x00\x00";
+// a => 3 and c => 4 in database 0
+const B: &[u8] = b"REDIS0006\xfe\x00\x00\x01a\x013\x00\x01c\x014\xff\x00\x00\x00\x00\x00\x00\x00\x00";
+
+// a => 5 and a stream s in database 0, in RDB v9
+const STREAM: &[u8] = b"REDIS0009\xfe\x00\x00\x01a\x015\x0f\x01s\x00\xff\x00\x00\x00\x00\x00\x00\x00\x00";
+
+/// empty directory `rmerger-cli-<name>-<pid>` in the temporary directory, with A.rdb and B.rdb
+fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("rmerger-cli-{}-{}", name, process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    fs::write(dir.join("A.rdb"), A).unwrap();
+    fs::write(dir.join("B.rdb"), B).unwrap();
+    dir
+}
+
+/// run rmerger in `dir` with `args`, writing `stdin` to its stdin
+fn rmerger(dir: &PathBuf, args: &[&str], stdin: &[u8]) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_rmerger"))
+        .current_dir(dir)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    child.stdin.take().unwrap().write_all(stdin).unwrap();
+    child.wait_with_output().unwrap()
+}
+
+/// (database, key) of the records of the RDB file `bytes`, sorted
+fn keys(bytes: &[u8]) -> Vec<(u32, String)> {
+    let rdb = parse_rdb(bytes).unwrap();
+    let mut keys: Vec<(u32, String)> = rdb.databases()
+        .flat_map(|db| db.records().map(move |r| (db.number(), String::from_utf8(Vec::<u8>::decode(&r.0).unwrap()).unwrap())))
+        .collect();
+    keys.sort();
+    keys
+}
+
+#[test]
+fn stdin_test() {
+    let dir = test_dir("stdin");
+    for args in &[&["-o", ".", "A.rdb", "-"][..], &["-o", ".", "--stdin", "A.rdb"][..], &["-o", ".", "--stdin-temp-file", "A.rdb", "-"][..]] {
+        let out = rmerger(&dir, args, B);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        let merged = fs::read(dir.join("MERGE.rdb")).unwrap();
+        assert_eq!(keys(&merged), vec![(0, "a".to_string()), (0, "c".to_string()), (0, "l".to_string()), (2, "b".to_string())]);
+        fs::remove_file(dir.join("MERGE.rdb")).unwrap();
+    }
+    // with no input file, stdin is read
+    let out = rmerger(&dir, &["-o", "."], B);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(keys(&fs::read(dir.join("MERGE.rdb")).unwrap()), vec![(0, "a".to_string()), (0, "c".to_string())]);
+    fs::remove_file(dir.join("MERGE.rdb")).unwrap();
+    let out = rmerger(&dir, &["-o", ".", "-", "-"], B);
+    assert!(!out.status.success());
+
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    gz.write_all(B).unwrap();
+    let gz = gz.finish().unwrap();
+    for args in &[&["-o", ".", "-"][..], &["-o", ".", "--stdin-temp-file", "-"][..]] {
+        let out = rmerger(&dir, args, &gz);
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        assert_eq!(keys(&fs::read(dir.join("MERGE.rdb")).unwrap()), vec![(0, "a".to_string()), (0, "c".to_string())]);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn dry_run_summary_test() {
+    let dir = test_dir("dry-run");
+    let out = rmerger(&dir, &["-o", ".", "--dry-run", "A.rdb", "B.rdb"], b"");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let summary = String::from_utf8(out.stdout).unwrap();
+    let row = |label: &str| -> Vec<String> {
+        let line = summary.lines().find(|l| l.starts_with(label)).unwrap_or_else(|| panic!("{}: {}", label, summary));
+        line[label.len()..].split_whitespace().map(|s| s.to_string()).collect()
+    };
+    assert_eq!(row("0, string"), vec!["2", "10"]);
+    assert_eq!(row("0, list"), vec!["1", "6"]);
+    assert_eq!(row("2, string"), vec!["1", "14"]);
+    assert_eq!(row("duplicates discarded"), vec!["1"]);
+    assert_eq!(row("bytes written"), vec!["0"]);
+
+    let mut names: Vec<String> = fs::read_dir(&dir).unwrap().map(|e| e.unwrap().file_name().to_string_lossy().to_string()).collect();
+    names.sort();
+    assert_eq!(names, vec!["A.rdb", "B.rdb"]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn stdout_test() {
+    let dir = test_dir("stdout");
+    for args in &[&["-o", ".", "--stdout", "A.rdb", "B.rdb"][..], &["--stdout", "--in-memory", "A.rdb", "B.rdb"][..]] {
+        let out = rmerger(&dir, args, b"");
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        assert_eq!(keys(&out.stdout), vec![(0, "a".to_string()), (0, "c".to_string()), (0, "l".to_string()), (2, "b".to_string())]);
+        assert!(!dir.join("MERGE.rdb").exists());
+    }
+    let out = rmerger(&dir, &["-o", ".", "--stdout", "--split-by-db", "A.rdb"], b"");
+    assert!(!out.status.success());
+    // --in-memory has no output directory
+    let out = rmerger(&dir, &["-o", ".", "--in-memory", "A.rdb"], b"");
+    assert_eq!(out.status.code(), Some(2));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn intersect_test() {
+    let dir = test_dir("intersect");
+    let out = rmerger(&dir, &["-o", ".", "--intersect", "--conflict", "last-wins", "A.rdb", "B.rdb"], b"");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let merged = fs::read(dir.join("MERGE.rdb")).unwrap();
+    assert_eq!(keys(&merged), vec![(0, "a".to_string())]);
+    // the value of the last file wins
+    assert!(merged.windows(4).any(|w| w == b"\x01a\x013"));
+
+    // the keys of a corrupted file are not used
+    let mut corrupted = B.to_vec();
+    let last = corrupted.len() - 1;
+    corrupted[last] = 0x01;
+    fs::write(dir.join("C.rdb"), &corrupted).unwrap();
+    let out = rmerger(&dir, &["-o", ".", "--intersect", "A.rdb", "C.rdb"], b"");
+    assert!(String::from_utf8_lossy(&out.stderr).contains("checksum mismatch"));
+    assert!(!out.status.success());
+    // nor do records of unsupported value types stop it with --skip-unsupported
+    fs::write(dir.join("S.rdb"), STREAM).unwrap();
+    let out = rmerger(&dir, &["-o", ".", "--intersect", "--skip-unsupported", "A.rdb", "S.rdb"], b"");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(keys(&fs::read(dir.join("MERGE.rdb")).unwrap()), vec![(0, "a".to_string())]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn difference_test() {
+    let dir = test_dir("difference");
+    for &(files, ref expected) in &[(["A.rdb", "B.rdb"], vec![(0, "l".to_string()), (2, "b".to_string())]), (["B.rdb", "A.rdb"], vec![(0, "c".to_string())])] {
+        let out = rmerger(&dir, &["-o", ".", "--difference", files[0], files[1]], b"");
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        assert_eq!(&keys(&fs::read(dir.join("MERGE.rdb")).unwrap()), expected);
+    }
+    // stdin is read only once, after the keys of the other files
+    let out = rmerger(&dir, &["-o", ".", "--difference", "-", "A.rdb"], B);
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(keys(&fs::read(dir.join("MERGE.rdb")).unwrap()), vec![(0, "c".to_string())]);
+    assert!(!rmerger(&dir, &["-o", ".", "--difference", "A.rdb", "-"], B).status.success());
+
+    // the keys of a corrupted file are not used, and --skip-unsupported skips a stream
+    let mut corrupted = B.to_vec();
+    let last = corrupted.len() - 1;
+    corrupted[last] = 0x01;
+    fs::write(dir.join("C.rdb"), &corrupted).unwrap();
+    let out = rmerger(&dir, &["-o", ".", "--difference", "A.rdb", "C.rdb"], b"");
+    assert!(String::from_utf8_lossy(&out.stderr).contains("checksum mismatch"));
+    assert!(!out.status.success());
+    fs::write(dir.join("S.rdb"), STREAM).unwrap();
+    let out = rmerger(&dir, &["-o", ".", "--difference", "--skip-unsupported", "A.rdb", "S.rdb"], b"");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert_eq!(keys(&fs::read(dir.join("MERGE.rdb")).unwrap()), vec![(0, "l".to_string()), (2, "b".to_string())]);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn lock_test() {
+    let dir = test_dir("lock");
+    fs::create_dir(dir.join("work")).unwrap();
+    let out = rmerger(&dir, &["-o", ".", "--work-dir", "work", "--delete-parts", "A.rdb", "B.rdb"], b"");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    assert!(!dir.join("work").exists());
+    assert!(!dir.join(".rmerger.lock").exists());
+
+    // a second run fails on the lock of the work directory
+    fs::write(dir.join(".rmerger.lock"), b"").unwrap();
+    let lock = fs::File::open(dir.join(".rmerger.lock")).unwrap();
+    lock.try_lock().unwrap();
+    let out = rmerger(&dir, &["-o", ".", "A.rdb"], b"");
+    assert!(!out.status.success());
+    assert!(String::from_utf8_lossy(&out.stderr).contains("another rmerger is using the work directory"));
+    assert!(rmerger(&dir, &["-o", ".", "--dry-run", "A.rdb"], b"").status.success());
+    assert!(rmerger(&dir, &["-o", ".", "--no-lock", "A.rdb"], b"").status.success());
+    drop(lock);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn diff_exit_status_test() {
+    let dir = test_dir("diff");
+    fs::write(dir.join("C.rdb"), &B[..B.len() - 12]).unwrap();
+    for &(a, b, status) in &[("A.rdb", "A.rdb", 0), ("A.rdb", "B.rdb", 1), ("A.rdb", "MISSING.rdb", 2), ("C.rdb", "A.rdb", 2)] {
+        let out = rmerger(&dir, &["diff", a, b], b"");
+        assert_eq!(out.status.code(), Some(status), "{} {}: {}", a, b, String::from_utf8_lossy(&out.stderr));
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn csv_stdout_test() {
+    let dir = test_dir("csv-stdout");
+    let out = rmerger(&dir, &["--csv", "-", "A.rdb"], b"");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let csv = String::from_utf8(out.stdout).unwrap();
+    assert!(csv.starts_with("db,key,"), "{}", csv);
+    assert_eq!(csv.lines().count(), 4);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn json_stdout_test() {
+    let dir = test_dir("json-stdout");
+    let out = rmerger(&dir, &["--json", "A.rdb", "B.rdb"], b"");
+    assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+    let json = String::from_utf8(out.stdout).unwrap();
+    for line in json.lines() {
+        serde_json::from_str::<serde_json::Value>(line).unwrap_or_else(|e| panic!("{}: {}", e, line));
+    }
+    assert_eq!(json.lines().count(), 5);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn flatten_test() {
+    let dir = test_dir("flatten");
+    for &(args, db) in &[(&["--flatten", "A.rdb", "B.rdb"][..], 0), (&["--flatten=3", "A.rdb", "B.rdb"][..], 3)] {
+        let out = rmerger(&dir, args, b"");
+        assert!(out.status.success(), "{}", String::from_utf8_lossy(&out.stderr));
+        let merged = fs::read(dir.join("MERGE.rdb")).unwrap();
+        let names: Vec<(u32, String)> = ["a", "b", "c", "l"].iter().map(|k| (db, k.to_string())).collect();
+        assert_eq!(keys(&merged), names);
+    }
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,40 @@
+/*
+ * smoke test of the C API of the ffi feature:
+ *
+ *   cargo rustc --lib --features ffi --crate-type cdylib,staticlib
+ *   cc -Iinclude tests/ffi_test.c -Ltarget/debug -lrmerger -o target/ffi_test
+ *   LD_LIBRARY_PATH=target/debug target/ffi_test
+ */
+#include <assert.h>
+#include <stdio.h>
+#include <string.h>
+
+#include "rmerger.h"
+
+int main(void) {
+    /* database 0: a => "1" and l => ["x"], database 2: b\0c => "2" */
+    static const uint8_t file[] =
+        "REDIS0006"
+        "\xfe\x00" "\x00\x01" "a" "\x01" "1" "\x01\x01" "l" "\x01\x01" "x"
+        "\xfe\x02" "\x00\x03" "b\0c" "\x01" "2"
+        "\xff" "\x00\x00\x00\x00\x00\x00\x00\x00";
+
+    OpaqueRDB *rdb = rmerger_parse(file, sizeof(file) - 1);
+    assert(rdb != NULL);
+    assert(rmerger_record_count(rdb, 0) == 2);
+    assert(rmerger_record_count(rdb, 1) == 0);
+    assert(rmerger_record_count(rdb, 2) == 1);
+    assert(strcmp(rmerger_get_key(rdb, 0, 1), "l") == 0);
+    assert(rmerger_get_key_len(rdb, 2, 0) == 3);
+    assert(memcmp(rmerger_get_key(rdb, 2, 0), "b\0c", 4) == 0);
+    assert(rmerger_get_value_type(rdb, 0, 0) == 0x00);
+    assert(rmerger_get_value_type(rdb, 0, 1) == 0x01);
+    assert(rmerger_get_key(rdb, 0, 2) == NULL);
+    assert(rmerger_get_key_len(rdb, 0, 2) == 0);
+    assert(rmerger_get_value_type(rdb, 1, 0) == RMERGER_NO_VALUE_TYPE);
+    rmerger_free(rdb);
+
+    assert(rmerger_parse(file, 12) == NULL);
+    printf("ffi_test: ok\n");
+    return 0;
+}
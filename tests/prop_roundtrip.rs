@@ -0,0 +1,363 @@
+extern crate proptest;
+extern crate rmerger;
+
+use std::collections::BTreeSet;
+use proptest::prelude::*;
+use proptest::collection::vec;
+use proptest::sample::select;
+use rmerger::parser::{
+    RDBDec, RDBSer, Record, OwnedRecord, EncodedLength, EncodedString, EncodedValue, ExpiryTime,
+    EncodedList, EncodedSet, EncodedSortedset, EncodedHashmap, EncodedModule,
+    EncodedZiplist, EncodedIntset, EncodedSortedsetZiplist, EncodedHashmapZiplist,
+    encode_length, ziplist_encode, record, database_number,
+};
+
+// The parsed types borrow the buffer they are parsed from, so the strategies build owned
+// counterparts holding the bytes of every field, which `borrow` turns into the parsed types.
+// `OwnedRecord` only holds serialized bytes and is checked through `OwnedRecord::from`.
+
+#[derive(Debug, Clone)]
+enum OwnedEncodedLength {
+    I(u32, Vec<u8>),
+    S(u8, Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+enum OwnedEncodedString {
+    Raw(OwnedEncodedLength, Vec<u8>),
+    Int(OwnedEncodedLength, Vec<u8>),
+    /// lengths and bytes of the compressed string, and the string it decompresses to
+    Lzf(OwnedEncodedLength, OwnedEncodedLength, OwnedEncodedLength, Vec<u8>, Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+enum OwnedEncodedValue {
+    V0(OwnedEncodedString),
+    V1(OwnedEncodedLength, Vec<OwnedEncodedString>),
+    V2(OwnedEncodedLength, Vec<OwnedEncodedString>),
+    /// members with their scores as strings
+    V3(OwnedEncodedLength, Vec<(OwnedEncodedString, Vec<u8>)>),
+    V4(OwnedEncodedLength, Vec<(OwnedEncodedString, OwnedEncodedString)>),
+    /// module type id and the bytes of the id and the opcodes
+    V7(u64, Vec<u8>),
+    VA(OwnedEncodedString),
+    VB(OwnedEncodedString),
+    VC(OwnedEncodedString),
+    VD(OwnedEncodedString),
+}
+
+#[derive(Debug, Clone)]
+enum OwnedExpiryTime {
+    MilliSec(Vec<u8>),
+    Sec(Vec<u8>),
+}
+
+#[derive(Debug, Clone)]
+struct OwnedTestRecord(OwnedEncodedString, OwnedEncodedValue, Option<OwnedExpiryTime>);
+
+impl OwnedEncodedLength {
+    fn borrow(&self) -> EncodedLength<'_> {
+        match self {
+            &OwnedEncodedLength::I(n, ref b) => EncodedLength::I(n, b),
+            &OwnedEncodedLength::S(t, ref b) => EncodedLength::S(t, b),
+        }
+    }
+}
+
+impl OwnedEncodedString {
+    fn borrow(&self) -> EncodedString<'_> {
+        match self {
+            &OwnedEncodedString::Raw(ref l, ref v)                 => EncodedString::Raw(l.borrow(), v),
+            &OwnedEncodedString::Int(ref l, ref v)                 => EncodedString::Int(l.borrow(), v),
+            &OwnedEncodedString::Lzf(ref l, ref c, ref u, ref v, _) => EncodedString::Lzf(l.borrow(), c.borrow(), u.borrow(), v),
+        }
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut v = Vec::new();
+        self.borrow().ser(&mut v).unwrap();
+        v
+    }
+}
+
+impl OwnedEncodedValue {
+    fn borrow(&self) -> EncodedValue<'_> {
+        match self {
+            &OwnedEncodedValue::V0(ref s)        => EncodedValue::V0(s.borrow()),
+            &OwnedEncodedValue::V1(ref l, ref v) => EncodedValue::V1(EncodedList(l.borrow(), v.iter().map(|s| s.borrow()).collect())),
+            &OwnedEncodedValue::V2(ref l, ref v) => EncodedValue::V2(EncodedSet(l.borrow(), v.iter().map(|s| s.borrow()).collect())),
+            &OwnedEncodedValue::V3(ref l, ref v) =>
+                EncodedValue::V3(EncodedSortedset(l.borrow(), v.iter().map(|&(ref m, ref f)| (m.borrow(), f.len() as u8, &f[..])).collect())),
+            &OwnedEncodedValue::V4(ref l, ref v) =>
+                EncodedValue::V4(EncodedHashmap(l.borrow(), v.iter().map(|&(ref k, ref v)| (k.borrow(), v.borrow())).collect())),
+            &OwnedEncodedValue::V7(id, ref b)    => EncodedValue::V7(EncodedModule(id, b)),
+            &OwnedEncodedValue::VA(ref s)        => EncodedValue::VA(EncodedZiplist(s.borrow())),
+            &OwnedEncodedValue::VB(ref s)        => EncodedValue::VB(EncodedIntset(s.borrow())),
+            &OwnedEncodedValue::VC(ref s)        => EncodedValue::VC(EncodedSortedsetZiplist(s.borrow())),
+            &OwnedEncodedValue::VD(ref s)        => EncodedValue::VD(EncodedHashmapZiplist(s.borrow())),
+        }
+    }
+}
+
+impl OwnedTestRecord {
+    fn borrow(&self) -> Record<'_> {
+        let expiry = match self.2 {
+            Some(OwnedExpiryTime::MilliSec(ref b)) => Some(ExpiryTime::MilliSec(b)),
+            Some(OwnedExpiryTime::Sec(ref b))      => Some(ExpiryTime::Sec(b)),
+            None                                   => None,
+        };
+        Record(self.0.borrow(), self.1.borrow(), expiry)
+    }
+}
+
+/// every valid encoding of the length `n`: 6, 14, 32 and 64 bits
+fn length_encodings(n: u32) -> Vec<Vec<u8>> {
+    let mut v = Vec::new();
+    if n < 1 << 6 {
+        v.push(vec![n as u8]);
+    }
+    if n < 1 << 14 {
+        v.push(vec![0x40 | (n >> 8) as u8, n as u8]);
+    }
+    v.push([&[0x80][..], &n.to_be_bytes()].concat());
+    v.push([&[0x81][..], &u64::from(n).to_be_bytes()].concat());
+    v
+}
+
+/// length of each of the 6, 14 and 32-bit encodings
+fn any_length() -> BoxedStrategy<u32> {
+    prop_oneof![0..1u32 << 6, 1u32 << 6..1 << 14, any::<u32>()].boxed()
+}
+
+fn length(n: usize) -> BoxedStrategy<OwnedEncodedLength> {
+    select(length_encodings(n as u32)).prop_map(move |b| OwnedEncodedLength::I(n as u32, b)).boxed()
+}
+
+/// LZF compression of `input`, with back references to repeated bytes of 3 or more
+fn lzf_compress(input: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut literal: Vec<u8> = Vec::new();
+    let flush = |out: &mut Vec<u8>, literal: &mut Vec<u8>| {
+        for chunk in literal.chunks(32) {
+            out.push(chunk.len() as u8 - 1);
+            out.extend_from_slice(chunk);
+        }
+        literal.clear();
+    };
+    let mut i = 0;
+    while i < input.len() {
+        // longest match starting up to 8192 bytes back, which may overlap position i
+        let (mut best, mut from) = (0, 0);
+        for j in i.saturating_sub(8192)..i {
+            let len = (0..264.min(input.len() - i)).take_while(|&k| input[j + k] == input[i + k]).count();
+            if len > best {
+                best = len;
+                from = j;
+            }
+        }
+        if best < 3 {
+            literal.push(input[i]);
+            i += 1;
+            continue;
+        }
+        flush(&mut out, &mut literal);
+        let (len, offset) = (best - 2, i - from - 1);
+        if len < 7 {
+            out.push((len << 5 | offset >> 8) as u8);
+        } else {
+            out.push((7 << 5 | offset >> 8) as u8);
+            out.push((len - 7) as u8);
+        }
+        out.push(offset as u8);
+        i += best;
+    }
+    flush(&mut out, &mut literal);
+    out
+}
+
+/// bytes with repeats for the back references of LZF
+fn bytes() -> BoxedStrategy<Vec<u8>> {
+    prop_oneof![
+        vec(any::<u8>(), 0..100),
+        vec(select(b"ab\x00".to_vec()), 0..300),
+    ].boxed()
+}
+
+/// `s` as a raw or an LZF compressed string
+fn string_of(s: BoxedStrategy<Vec<u8>>) -> BoxedStrategy<OwnedEncodedString> {
+    prop_oneof![
+        s.clone().prop_flat_map(|v| (length(v.len()), Just(v)))
+            .prop_map(|(l, v)| OwnedEncodedString::Raw(l, v)),
+        lzf_string(s),
+    ].boxed()
+}
+
+fn lzf_string(s: BoxedStrategy<Vec<u8>>) -> BoxedStrategy<OwnedEncodedString> {
+    s.prop_flat_map(|v| {
+        let c = lzf_compress(&v);
+        (length(c.len()), length(v.len()), Just(c), Just(v))
+    }).prop_map(|(c, u, compressed, v)| {
+        OwnedEncodedString::Lzf(OwnedEncodedLength::S(3, vec![0xc3]), c, u, compressed, v)
+    }).boxed()
+}
+
+/// integer of 8, 16 or 32 bits
+fn int_string() -> BoxedStrategy<OwnedEncodedString> {
+    (0..3u8).prop_flat_map(|w| (Just(w), vec(any::<u8>(), 1 << w)))
+        .prop_map(|(w, v)| OwnedEncodedString::Int(OwnedEncodedLength::S(w, vec![0xc0 | w]), v))
+        .boxed()
+}
+
+fn elements() -> BoxedStrategy<Vec<Vec<u8>>> {
+    vec(prop_oneof![vec(any::<u8>(), 0..20), any::<i64>().prop_map(|i| i.to_string().into_bytes())], 0..10).boxed()
+}
+
+fn ziplist_of(elements: BoxedStrategy<Vec<Vec<u8>>>) -> BoxedStrategy<OwnedEncodedString> {
+    string_of(elements.prop_map(|e| ziplist_encode(&e.iter().map(|e| &e[..]).collect::<Vec<_>>())).boxed())
+}
+
+/// intset of 16, 32 or 64-bit integers
+fn intset() -> BoxedStrategy<OwnedEncodedString> {
+    let set = select(vec![2u32, 4, 8]).prop_flat_map(|w| {
+        let max = i64::MAX >> (64 - 8 * w);
+        (Just(w), vec(-max - 1..=max, 0..10))
+    }).prop_map(|(w, ints)| {
+        let ints: BTreeSet<i64> = ints.into_iter().collect();
+        let mut v = [w.to_le_bytes(), (ints.len() as u32).to_le_bytes()].concat();
+        for i in ints {
+            v.extend_from_slice(&i.to_le_bytes()[..w as usize]);
+        }
+        v
+    });
+    string_of(set.boxed())
+}
+
+/// 64-bit integer of a module value, in the 64-bit length encoding unless it fits in 32 bits
+fn module_u64() -> BoxedStrategy<Vec<u8>> {
+    prop_oneof![
+        any::<u64>().prop_map(|n| [&[0x81][..], &n.to_be_bytes()].concat()),
+        any::<u32>().prop_flat_map(|n| select(length_encodings(n))),
+    ].boxed()
+}
+
+/// module type id and the opcodes of its value, up to the EOF opcode
+fn module() -> BoxedStrategy<OwnedEncodedValue> {
+    let opcode = prop_oneof![
+        (1..3u32, module_u64()).prop_map(|(op, n)| [encode_length(op), n].concat()),
+        vec(any::<u8>(), 4).prop_map(|f| [encode_length(3), f].concat()),
+        vec(any::<u8>(), 8).prop_map(|d| [encode_length(4), d].concat()),
+        any::<OwnedEncodedString>().prop_map(|s| [encode_length(5), s.to_bytes()].concat()),
+    ];
+    (any::<u64>(), vec(opcode, 0..5)).prop_map(|(id, opcodes)| {
+        let bytes = [[&[0x81][..], &id.to_be_bytes()].concat(), opcodes.concat(), encode_length(0)].concat();
+        OwnedEncodedValue::V7(id, bytes)
+    }).boxed()
+}
+
+/// score of a sorted set member as a string, shorter than the 253 bytes of its length byte
+fn score() -> BoxedStrategy<Vec<u8>> {
+    prop_oneof![
+        any::<f64>().prop_map(|f| format!("{:e}", f).into_bytes()),
+        any::<i32>().prop_map(|i| i.to_string().into_bytes()),
+    ].boxed()
+}
+
+impl Arbitrary for OwnedEncodedString {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        prop_oneof![string_of(bytes()), int_string()].boxed()
+    }
+}
+
+impl Arbitrary for OwnedEncodedValue {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let strings = || vec(any::<OwnedEncodedString>(), 0..8).prop_flat_map(|v| (length(v.len()), Just(v)));
+        prop_oneof![
+            any::<OwnedEncodedString>().prop_map(OwnedEncodedValue::V0),
+            strings().prop_map(|(l, v)| OwnedEncodedValue::V1(l, v)),
+            strings().prop_map(|(l, v)| OwnedEncodedValue::V2(l, v)),
+            vec((any::<OwnedEncodedString>(), score()), 0..8).prop_flat_map(|v| (length(v.len()), Just(v)))
+                .prop_map(|(l, v)| OwnedEncodedValue::V3(l, v)),
+            vec(any::<(OwnedEncodedString, OwnedEncodedString)>(), 0..8).prop_flat_map(|v| (length(v.len()), Just(v)))
+                .prop_map(|(l, v)| OwnedEncodedValue::V4(l, v)),
+            module(),
+            ziplist_of(elements()).prop_map(OwnedEncodedValue::VA),
+            intset().prop_map(OwnedEncodedValue::VB),
+            ziplist_of(vec((vec(any::<u8>(), 0..20), score()), 0..8)
+                       .prop_map(|v| v.into_iter().flat_map(|(m, s)| vec![m, s]).collect()).boxed())
+                .prop_map(OwnedEncodedValue::VC),
+            ziplist_of(elements().prop_map(|mut e| { e.truncate(e.len() / 2 * 2); e }).boxed())
+                .prop_map(OwnedEncodedValue::VD),
+        ].boxed()
+    }
+}
+
+impl Arbitrary for OwnedTestRecord {
+    type Parameters = ();
+    type Strategy = BoxedStrategy<Self>;
+
+    fn arbitrary_with(_: ()) -> Self::Strategy {
+        let expiry = prop::option::of(prop_oneof![
+            vec(any::<u8>(), 8).prop_map(OwnedExpiryTime::MilliSec),
+            vec(any::<u8>(), 4).prop_map(OwnedExpiryTime::Sec),
+        ]);
+        (any::<OwnedEncodedString>(), any::<OwnedEncodedValue>(), expiry)
+            .prop_map(|(k, v, e)| OwnedTestRecord(k, v, e))
+            .boxed()
+    }
+}
+
+proptest! {
+    /// a serialized record parses back into the same record, also through `OwnedRecord`
+    #[test]
+    fn record_roundtrip(r in any::<OwnedTestRecord>()) {
+        let expected = r.borrow();
+        let mut bytes = Vec::new();
+        prop_assert_eq!(expected.ser(&mut bytes).unwrap(), bytes.len());
+        let (rest, parsed) = record(&bytes).unwrap();
+        prop_assert!(rest.is_empty());
+        prop_assert_eq!(&parsed, &expected);
+        let owned = OwnedRecord::from(&parsed);
+        prop_assert_eq!(owned.record(), expected);
+    }
+
+    /// an LZF string decompresses to the string it was compressed from
+    #[test]
+    fn lzf_roundtrip(s in lzf_string(bytes())) {
+        let plain = match s {
+            OwnedEncodedString::Lzf(_, _, _, _, ref v) => v.clone(),
+            _                                          => unreachable!(),
+        };
+        let decoded: Vec<u8> = RDBDec::decode(&s.borrow()).unwrap();
+        prop_assert_eq!(decoded, plain);
+    }
+
+    /// `encode_length` is the inverse of parsing its output
+    #[test]
+    fn encode_length_roundtrip(n in any_length()) {
+        let bytes = encode_length(n);
+        let input = [&[0xfe][..], &bytes].concat();
+        let (rest, parsed) = database_number(&input).unwrap();
+        prop_assert!(rest.is_empty());
+        prop_assert_eq!(parsed.1, n);
+        prop_assert_eq!(encode_length(parsed.1), bytes);
+    }
+
+    /// every valid encoding of a length parses to it and serializes back unchanged, and
+    /// `encode_length` gives the shortest one
+    #[test]
+    fn length_encodings_roundtrip((n, bytes) in any_length().prop_flat_map(|n| (Just(n), select(length_encodings(n))))) {
+        let input = [&[0xfe][..], &bytes].concat();
+        let (_, parsed) = database_number(&input).unwrap();
+        prop_assert_eq!(parsed.1, n);
+        let mut out = Vec::new();
+        parsed.0.ser(&mut out).unwrap();
+        prop_assert_eq!(&out, &bytes);
+        prop_assert!(encode_length(n).len() <= bytes.len());
+    }
+}
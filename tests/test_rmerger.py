@@ -0,0 +1,34 @@
+# smoke test of the Python module: maturin develop && python -m pytest tests/test_rmerger.py
+import pytest
+
+from rmerger import PyRDB
+
+# a => 1 and l => [x] in database 0, b => 2 expiring at 1000 ms in database 2
+A = b"REDIS0006\xfe\x00\x00\x01a\x011\x01\x01l\x01\x01x\xfe\x02\xfc\xe8\x03\x00\x00\x00\x00\x00\x00\x00\x01b\x012\xff\x00\x00\x00\x00\x00\x00\x00\x00"
+# a => 3 and c => 4 in database 0
+B = b"REDIS0006\xfe\x00\x00\x01a\x013\x00\x01c\x014\xff\x00\x00\x00\x00\x00\x00\x00\x00"
+
+
+def keys(rdb):
+    return [(db.number, r.key) for db in rdb.databases for r in db.records]
+
+
+def test_parse():
+    rdb = PyRDB.parse(A)
+    assert rdb.version == 6
+    assert keys(rdb) == [(0, b"a"), (0, b"l"), (2, b"b")]
+    record = rdb.databases[0].records[1]
+    assert (record.value_type, record.expiry_ms) == (1, None)
+    assert rdb.databases[1].records[0].expiry_ms == 1000
+    assert rdb.to_bytes() == A
+
+
+def test_parse_error():
+    with pytest.raises(ValueError):
+        PyRDB.parse(A[:12])
+
+
+def test_merge():
+    merged = PyRDB.merge([PyRDB.parse(A), PyRDB.parse(B)])
+    assert sorted(keys(merged)) == [(0, b"a"), (0, b"c"), (0, b"l"), (2, b"b")]
+    assert keys(PyRDB.parse(merged.to_bytes())) == keys(merged)
@@ -0,0 +1,55 @@
+import init, { parse_rdb } from "../pkg/rmerger.js";
+
+const drop = document.getElementById("drop");
+const summary = document.getElementById("summary");
+const error = document.getElementById("error");
+const table = document.getElementById("records");
+
+function cell(row, text, number) {
+  const td = row.insertCell();
+  td.textContent = text;
+  if (number) {
+    td.className = "number";
+  }
+}
+
+function show(name, rdb) {
+  summary.textContent = `${name}: RDB version ${rdb.version}, databases ${rdb.databases.join(", ")}, ${rdb.records.length} records`;
+  const body = table.tBodies[0];
+  body.replaceChildren();
+  for (const r of rdb.records) {
+    const row = body.insertRow();
+    cell(row, r.db, true);
+    cell(row, r.key);
+    cell(row, r.type);
+    cell(row, r.elements, true);
+    cell(row, r.bytes, true);
+    cell(row, r.expiry_ms === null ? "" : new Date(r.expiry_ms).toISOString());
+  }
+  table.hidden = false;
+}
+
+async function inspect(file) {
+  error.textContent = "";
+  try {
+    show(file.name, parse_rdb(new Uint8Array(await file.arrayBuffer())));
+  } catch (e) {
+    summary.textContent = "";
+    table.hidden = true;
+    error.textContent = `${file.name}: ${e}`;
+  }
+}
+
+await init();
+drop.addEventListener("dragover", (e) => {
+  e.preventDefault();
+  drop.classList.add("over");
+});
+drop.addEventListener("dragleave", () => drop.classList.remove("over"));
+drop.addEventListener("drop", (e) => {
+  e.preventDefault();
+  drop.classList.remove("over");
+  if (e.dataTransfer.files.length > 0) {
+    inspect(e.dataTransfer.files[0]);
+  }
+});